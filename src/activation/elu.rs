@@ -0,0 +1,23 @@
+use super::ActivationFunction;
+
+const ALPHA: f32 = 1.;
+
+pub struct Elu;
+
+impl ActivationFunction for Elu {
+    fn func(x: f32) -> f32 {
+        if x >= 0. {
+            x
+        } else {
+            ALPHA * x.exp_m1()
+        }
+    }
+
+    fn deriv(x: f32) -> f32 {
+        if x >= 0. {
+            1.
+        } else {
+            ALPHA * x.exp()
+        }
+    }
+}
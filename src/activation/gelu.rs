@@ -0,0 +1,21 @@
+use super::ActivationFunction;
+
+// sqrt(2 / pi)
+const K: f32 = 0.797_884_6;
+const C: f32 = 0.044_715;
+
+pub struct Gelu;
+
+// tanh approximation, std has no erf for f32
+impl ActivationFunction for Gelu {
+    fn func(x: f32) -> f32 {
+        let t = (K * (x + C * x.powi(3))).tanh();
+        0.5 * x * (1. + t)
+    }
+
+    fn deriv(x: f32) -> f32 {
+        let t = (K * (x + C * x.powi(3))).tanh();
+        let dt = (1. - t * t) * K * (1. + 3. * C * x * x);
+        0.5 * (1. + t) + 0.5 * x * dt
+    }
+}
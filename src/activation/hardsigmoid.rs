@@ -0,0 +1,17 @@
+use super::ActivationFunction;
+
+pub struct HardSigmoid;
+
+impl ActivationFunction for HardSigmoid {
+    fn func(x: f32) -> f32 {
+        (x / 6. + 0.5).clamp(0., 1.)
+    }
+
+    fn deriv(x: f32) -> f32 {
+        if x > -3. && x < 3. {
+            1. / 6.
+        } else {
+            0.
+        }
+    }
+}
@@ -0,0 +1,23 @@
+use super::ActivationFunction;
+
+const SLOPE: f32 = 0.01;
+
+pub struct LeakyRelu;
+
+impl ActivationFunction for LeakyRelu {
+    fn func(x: f32) -> f32 {
+        if x >= 0. {
+            x
+        } else {
+            SLOPE * x
+        }
+    }
+
+    fn deriv(x: f32) -> f32 {
+        if x >= 0. {
+            1.
+        } else {
+            SLOPE
+        }
+    }
+}
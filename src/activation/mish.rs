@@ -0,0 +1,16 @@
+use super::sigmoid::Sigmoid;
+use super::softplus::softplus;
+use super::ActivationFunction;
+
+pub struct Mish;
+
+impl ActivationFunction for Mish {
+    fn func(x: f32) -> f32 {
+        x * softplus(x).tanh()
+    }
+
+    fn deriv(x: f32) -> f32 {
+        let t = softplus(x).tanh();
+        t + x * (1. - t * t) * Sigmoid::func(x)
+    }
+}
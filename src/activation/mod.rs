@@ -1,9 +1,19 @@
 use nalgebra::SMatrix;
 
+pub mod elu;
+pub mod gelu;
+pub mod hardsigmoid;
+pub mod leakyrelu;
+pub mod mish;
 pub mod noact;
+pub mod prelu;
 pub mod relu;
+pub mod selu;
 pub mod sigmoid;
+pub mod silu;
 //pub mod softmax;
+pub mod softplus;
+pub mod swish;
 pub mod tanh;
 
 pub trait ActivationFunction {
@@ -11,6 +21,14 @@ pub trait ActivationFunction {
     fn deriv(x: f32) -> f32;
 }
 
+// activation with one learnable parameter per unit
+pub trait ParametricActivation {
+    fn init() -> f32;
+    fn func(x: f32, p: f32) -> f32;
+    fn deriv(x: f32, p: f32) -> f32;
+    fn deriv_param(x: f32, p: f32) -> f32;
+}
+
 pub fn func_all<
     const R: usize,
     const C: usize,
@@ -34,3 +52,72 @@ pub fn deriv_all<
     x.iter_mut().for_each(|xi| *xi = F::deriv(*xi));
     x
 }
+
+#[test]
+fn test_deriv_finite_diff() {
+    fn check<F: ActivationFunction>(name: &str) {
+        const H: f32 = 1e-3;
+        // stay away from the kinks at 0 and +-3
+        let points =
+            [-4.2, -2.5, -1.3, -0.4, 0.3, 1.1, 2.7, 4.5];
+        for x in points {
+            let numeric = (F::func(x + H) - F::func(x - H))
+                / (2. * H);
+            let analytic = F::deriv(x);
+            assert!(
+                (numeric - analytic).abs() < 1e-2,
+                "{name}: deriv({x}) = {analytic}, finite \
+                 difference gives {numeric}"
+            );
+        }
+    }
+
+    check::<noact::NoActivation>("noact");
+    check::<relu::Relu>("relu");
+    check::<sigmoid::Sigmoid>("sigmoid");
+    check::<tanh::Tanh>("tanh");
+    check::<leakyrelu::LeakyRelu>("leakyrelu");
+    check::<elu::Elu>("elu");
+    check::<selu::Selu>("selu");
+    check::<gelu::Gelu>("gelu");
+    check::<silu::Silu>("silu");
+    check::<softplus::Softplus>("softplus");
+    check::<mish::Mish>("mish");
+    check::<hardsigmoid::HardSigmoid>("hardsigmoid");
+}
+
+#[test]
+fn test_param_deriv_finite_diff() {
+    fn check<F: ParametricActivation>(name: &str) {
+        const H: f32 = 1e-3;
+        let points = [-2.5, -1.3, -0.4, 0.3, 1.1, 2.7];
+        let params = [-0.5, 0.1, F::init(), 1.7];
+        for x in points {
+            for p in params {
+                let numeric = (F::func(x + H, p)
+                    - F::func(x - H, p))
+                    / (2. * H);
+                let analytic = F::deriv(x, p);
+                assert!(
+                    (numeric - analytic).abs() < 1e-2,
+                    "{name}: deriv({x}, {p}) = \
+                     {analytic}, finite difference gives \
+                     {numeric}"
+                );
+                let numeric = (F::func(x, p + H)
+                    - F::func(x, p - H))
+                    / (2. * H);
+                let analytic = F::deriv_param(x, p);
+                assert!(
+                    (numeric - analytic).abs() < 1e-2,
+                    "{name}: deriv_param({x}, {p}) = \
+                     {analytic}, finite difference gives \
+                     {numeric}"
+                );
+            }
+        }
+    }
+
+    check::<prelu::PRelu>("prelu");
+    check::<swish::Swish>("swish");
+}
@@ -0,0 +1,34 @@
+use super::ParametricActivation;
+
+// leaky relu with a learnable negative slope
+pub struct PRelu;
+
+impl ParametricActivation for PRelu {
+    fn init() -> f32 {
+        0.25
+    }
+
+    fn func(x: f32, a: f32) -> f32 {
+        if x >= 0. {
+            x
+        } else {
+            a * x
+        }
+    }
+
+    fn deriv(x: f32, a: f32) -> f32 {
+        if x >= 0. {
+            1.
+        } else {
+            a
+        }
+    }
+
+    fn deriv_param(x: f32, _: f32) -> f32 {
+        if x >= 0. {
+            0.
+        } else {
+            x
+        }
+    }
+}
@@ -0,0 +1,25 @@
+use super::ActivationFunction;
+
+// constants from "Self-Normalizing Neural Networks"
+const LAMBDA: f32 = 1.050_700_9;
+const ALPHA: f32 = 1.673_263_2;
+
+pub struct Selu;
+
+impl ActivationFunction for Selu {
+    fn func(x: f32) -> f32 {
+        if x >= 0. {
+            LAMBDA * x
+        } else {
+            LAMBDA * ALPHA * x.exp_m1()
+        }
+    }
+
+    fn deriv(x: f32) -> f32 {
+        if x >= 0. {
+            LAMBDA
+        } else {
+            LAMBDA * ALPHA * x.exp()
+        }
+    }
+}
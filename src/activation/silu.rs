@@ -0,0 +1,16 @@
+use super::sigmoid::Sigmoid;
+use super::ActivationFunction;
+
+// also known as Swish with beta = 1
+pub struct Silu;
+
+impl ActivationFunction for Silu {
+    fn func(x: f32) -> f32 {
+        x * Sigmoid::func(x)
+    }
+
+    fn deriv(x: f32) -> f32 {
+        let s = Sigmoid::func(x);
+        s * (1. + x * (1. - s))
+    }
+}
@@ -0,0 +1,19 @@
+use super::sigmoid::Sigmoid;
+use super::ActivationFunction;
+
+pub struct Softplus;
+
+pub(super) fn softplus(x: f32) -> f32 {
+    // ln(1 + e^x) without overflowing for large x
+    x.max(0.) + (-x.abs()).exp().ln_1p()
+}
+
+impl ActivationFunction for Softplus {
+    fn func(x: f32) -> f32 {
+        softplus(x)
+    }
+
+    fn deriv(x: f32) -> f32 {
+        Sigmoid::func(x)
+    }
+}
@@ -0,0 +1,26 @@
+use super::sigmoid::Sigmoid;
+use super::ActivationFunction;
+use super::ParametricActivation;
+
+// x * sigmoid(beta * x) with a learnable beta
+pub struct Swish;
+
+impl ParametricActivation for Swish {
+    fn init() -> f32 {
+        1.
+    }
+
+    fn func(x: f32, beta: f32) -> f32 {
+        x * Sigmoid::func(beta * x)
+    }
+
+    fn deriv(x: f32, beta: f32) -> f32 {
+        let s = Sigmoid::func(beta * x);
+        s + beta * x * s * (1. - s)
+    }
+
+    fn deriv_param(x: f32, beta: f32) -> f32 {
+        let s = Sigmoid::func(beta * x);
+        x * x * s * (1. - s)
+    }
+}
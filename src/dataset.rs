@@ -1,4 +1,6 @@
 use std::fs::File;
+use std::io::BufRead;
+use std::io::BufReader;
 
 use csv::ReaderBuilder;
 
@@ -50,3 +52,94 @@ pub fn get_data_csv<const X: usize>(
 
     Ok((x_train, y_train, x_test, y_test))
 }
+
+// words of a sentence and their tags
+pub type TaggedSentence = (Vec<String>, Vec<String>);
+
+// sentences of a CoNLL style file: one token per line with its
+// columns separated by whitespace, the word first and the tag in
+// tag_column, and an empty line after every sentence
+pub fn get_data_conll(
+    file_path: &str,
+    tag_column: usize,
+    train_test_ratio: f32,
+) -> anyhow::Result<(
+    Vec<TaggedSentence>,
+    Vec<TaggedSentence>,
+)> {
+    let file = File::open(file_path)?;
+    let mut sentences =
+        read_conll(BufReader::new(file), tag_column)?;
+    let train_limit = (sentences.len() as f32
+        * train_test_ratio) as usize;
+    let test = sentences.split_off(train_limit);
+    Ok((sentences, test))
+}
+
+pub fn read_conll<R: BufRead>(
+    reader: R,
+    tag_column: usize,
+) -> anyhow::Result<Vec<TaggedSentence>> {
+    let mut sentences = Vec::new();
+    let mut words = Vec::new();
+    let mut tags = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        let columns =
+            line.split_whitespace().collect::<Vec<_>>();
+        if columns.is_empty() {
+            if !words.is_empty() {
+                sentences.push((words, tags));
+                words = Vec::new();
+                tags = Vec::new();
+            }
+            continue;
+        }
+        // document separators are not tokens
+        if columns[0] == "-DOCSTART-" {
+            continue;
+        }
+        let tag =
+            columns.get(tag_column).ok_or_else(|| {
+                anyhow::anyhow!(
+                    "No tag column in line '{line}'"
+                )
+            })?;
+        words.push(columns[0].to_string());
+        tags.push(tag.to_string());
+    }
+    if !words.is_empty() {
+        sentences.push((words, tags));
+    }
+    Ok(sentences)
+}
+
+// a plain text file, the first train_test_ratio of its characters
+// for training and the rest for testing
+pub fn get_data_text(
+    file_path: &str,
+    train_test_ratio: f32,
+) -> anyhow::Result<(String, String)> {
+    let mut train = std::fs::read_to_string(file_path)?;
+    let train_limit = (train.chars().count() as f32
+        * train_test_ratio) as usize;
+    let split = train
+        .char_indices()
+        .nth(train_limit)
+        .map_or(train.len(), |(i, _)| i);
+    let test = train.split_off(split);
+    Ok((train, test))
+}
+
+#[test]
+fn test_read_conll() {
+    let text = "-DOCSTART- -X- -X- O\n\nEU NNP B-NP \
+                B-ORG\nrejects VBZ B-VP O\n\n\nPeter NNP \
+                B-NP B-PER\n";
+    let sentences = read_conll(text.as_bytes(), 3).unwrap();
+    assert_eq!(sentences.len(), 2);
+    assert_eq!(sentences[0].0, ["EU", "rejects"]);
+    assert_eq!(sentences[0].1, ["B-ORG", "O"]);
+    assert_eq!(sentences[1].1, ["B-PER"]);
+    assert!(read_conll(text.as_bytes(), 4).is_err());
+}
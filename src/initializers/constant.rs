@@ -0,0 +1,28 @@
+use nalgebra::SMatrix;
+
+use super::Initializer;
+
+pub struct Zeros;
+
+impl Initializer for Zeros {
+    fn init<const R: usize, const C: usize>(
+        _: usize,
+        _: usize,
+    ) -> SMatrix<f32, R, C> {
+        SMatrix::zeros()
+    }
+}
+
+// every entry set to NUM/DEN, e.g. the forget gate bias of an LSTM
+pub struct Constant<const NUM: usize, const DEN: usize>;
+
+impl<const NUM: usize, const DEN: usize> Initializer
+    for Constant<NUM, DEN>
+{
+    fn init<const R: usize, const C: usize>(
+        _: usize,
+        _: usize,
+    ) -> SMatrix<f32, R, C> {
+        SMatrix::repeat(NUM as f32 / DEN as f32)
+    }
+}
@@ -0,0 +1,30 @@
+use nalgebra::SMatrix;
+
+use super::sample_normal;
+use super::sample_uniform;
+use super::Initializer;
+
+// He / Kaiming, suited for relu layers
+pub struct HeUniform;
+
+impl Initializer for HeUniform {
+    fn init<const R: usize, const C: usize>(
+        fan_in: usize,
+        _: usize,
+    ) -> SMatrix<f32, R, C> {
+        let limit = (6. / fan_in as f32).sqrt();
+        sample_uniform(limit)
+    }
+}
+
+pub struct HeNormal;
+
+impl Initializer for HeNormal {
+    fn init<const R: usize, const C: usize>(
+        fan_in: usize,
+        _: usize,
+    ) -> SMatrix<f32, R, C> {
+        let std = (2. / fan_in as f32).sqrt();
+        sample_normal(std)
+    }
+}
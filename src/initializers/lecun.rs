@@ -0,0 +1,30 @@
+use nalgebra::SMatrix;
+
+use super::sample_normal;
+use super::sample_uniform;
+use super::Initializer;
+
+// suited for selu layers
+pub struct LecunUniform;
+
+impl Initializer for LecunUniform {
+    fn init<const R: usize, const C: usize>(
+        fan_in: usize,
+        _: usize,
+    ) -> SMatrix<f32, R, C> {
+        let limit = (3. / fan_in as f32).sqrt();
+        sample_uniform(limit)
+    }
+}
+
+pub struct LecunNormal;
+
+impl Initializer for LecunNormal {
+    fn init<const R: usize, const C: usize>(
+        fan_in: usize,
+        _: usize,
+    ) -> SMatrix<f32, R, C> {
+        let std = (1. / fan_in as f32).sqrt();
+        sample_normal(std)
+    }
+}
@@ -0,0 +1,38 @@
+use nalgebra::SMatrix;
+use rand::Rng;
+
+use crate::rng::with_rng;
+
+pub mod constant;
+pub mod he;
+pub mod lecun;
+pub mod orthogonal;
+pub mod uniform;
+pub mod xavier;
+
+pub trait Initializer {
+    fn init<const R: usize, const C: usize>(
+        fan_in: usize,
+        fan_out: usize,
+    ) -> SMatrix<f32, R, C>;
+}
+
+pub fn sample_uniform<const R: usize, const C: usize>(
+    limit: f32,
+) -> SMatrix<f32, R, C> {
+    let uniform =
+        rand_distr::Uniform::new_inclusive(-limit, limit);
+    with_rng(|rng| {
+        SMatrix::from_fn(|_, _| rng.sample(uniform))
+    })
+}
+
+pub fn sample_normal<const R: usize, const C: usize>(
+    std: f32,
+) -> SMatrix<f32, R, C> {
+    let normal = rand_distr::Normal::new(0., std)
+        .expect("Standard deviation must be finite");
+    with_rng(|rng| {
+        SMatrix::from_fn(|_, _| rng.sample(normal))
+    })
+}
@@ -0,0 +1,52 @@
+use nalgebra::SMatrix;
+
+use super::sample_normal;
+use super::Initializer;
+
+// (semi) orthogonal matrix, meant for recurrent weights so the
+// hidden state neither explodes nor vanishes at the start
+pub struct Orthogonal;
+
+impl Initializer for Orthogonal {
+    fn init<const R: usize, const C: usize>(
+        _: usize,
+        _: usize,
+    ) -> SMatrix<f32, R, C> {
+        // wide matrices get orthonormal rows instead of columns
+        if R < C {
+            return Self::init::<C, R>(0, 0).transpose();
+        }
+        // gram-schmidt over the columns
+        let mut w = sample_normal::<R, C>(1.);
+        for j in 0..C {
+            for k in 0..j {
+                let proj = w.column(j).dot(&w.column(k));
+                let wk = w.column(k).clone_owned();
+                w.column_mut(j).axpy(-proj, &wk, 1.);
+            }
+            w.column_mut(j).normalize_mut();
+        }
+        w
+    }
+}
+
+#[test]
+fn test_orthogonal() {
+    let w = Orthogonal::init::<6, 4>(4, 6);
+    let wtw = w.transpose() * w;
+    assert!(
+        (wtw - SMatrix::<f32, 4, 4>::identity())
+            .abs()
+            .max()
+            < 1e-4
+    );
+
+    let w = Orthogonal::init::<3, 5>(5, 3);
+    let wwt = w * w.transpose();
+    assert!(
+        (wwt - SMatrix::<f32, 3, 3>::identity())
+            .abs()
+            .max()
+            < 1e-4
+    );
+}
@@ -0,0 +1,19 @@
+use nalgebra::SMatrix;
+
+use super::sample_uniform;
+use super::Initializer;
+
+// U(-NUM/DEN, NUM/DEN) regardless of the layer size
+pub struct Uniform<const NUM: usize, const DEN: usize>;
+
+impl<const NUM: usize, const DEN: usize> Initializer
+    for Uniform<NUM, DEN>
+{
+    fn init<const R: usize, const C: usize>(
+        _: usize,
+        _: usize,
+    ) -> SMatrix<f32, R, C> {
+        let limit = NUM as f32 / DEN as f32;
+        sample_uniform(limit)
+    }
+}
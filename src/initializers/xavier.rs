@@ -0,0 +1,30 @@
+use nalgebra::SMatrix;
+
+use super::sample_normal;
+use super::sample_uniform;
+use super::Initializer;
+
+// Glorot & Bengio, suited for sigmoid and tanh layers
+pub struct XavierUniform;
+
+impl Initializer for XavierUniform {
+    fn init<const R: usize, const C: usize>(
+        fan_in: usize,
+        fan_out: usize,
+    ) -> SMatrix<f32, R, C> {
+        let limit = (6. / (fan_in + fan_out) as f32).sqrt();
+        sample_uniform(limit)
+    }
+}
+
+pub struct XavierNormal;
+
+impl Initializer for XavierNormal {
+    fn init<const R: usize, const C: usize>(
+        fan_in: usize,
+        fan_out: usize,
+    ) -> SMatrix<f32, R, C> {
+        let std = (2. / (fan_in + fan_out) as f32).sqrt();
+        sample_normal(std)
+    }
+}
@@ -1,7 +1,9 @@
 use nalgebra::SMatrix;
-use rand::Rng;
 
 use super::softmax2d::Softmax2d;
+use super::Parameters;
+use crate::initializers::uniform::Uniform;
+use crate::initializers::Initializer;
 use crate::optimizers::Optimizer;
 use crate::optimizers::OptimizerFactory;
 
@@ -20,8 +22,12 @@ pub struct Attention<
     v: SMatrix<f32, N, M>,
     z: SMatrix<f32, N, N>,
     s: SMatrix<f32, N, N>,
+    dwk: SMatrix<f32, M, D>,
+    dwq: SMatrix<f32, M, D>,
+    dwv: SMatrix<f32, M, M>,
     softmax2d: Softmax2d<N, N>,
-    optkq: <O as OptimizerFactory<M, D>>::Optimizer,
+    optk: <O as OptimizerFactory<M, D>>::Optimizer,
+    optq: <O as OptimizerFactory<M, D>>::Optimizer,
     optv: <O as OptimizerFactory<M, M>>::Optimizer,
 }
 
@@ -31,31 +37,30 @@ where
     O: OptimizerFactory<M, D> + OptimizerFactory<M, M>,
 {
     pub fn new() -> Self {
+        Self::with_init::<Uniform<1, 2>>()
+    }
+
+    // Wk, Wq and Wv initialized by I
+    pub fn with_init<I: Initializer>() -> Self {
         let x = SMatrix::zeros();
-        let mut wk = SMatrix::zeros();
-        let mut wq = SMatrix::zeros();
-        let mut wv = SMatrix::zeros();
+        let wk = I::init(M, D);
+        let wq = I::init(M, D);
+        let wv = I::init(M, M);
         let k = SMatrix::zeros();
         let q = SMatrix::zeros();
         let v = SMatrix::zeros();
         let z = SMatrix::zeros();
         let s = SMatrix::zeros();
-
-        let mut rng = rand::thread_rng();
-        let uniform = rand_distr::Uniform::new(-0.5, 0.5);
-        for i in 0..M {
-            for j in 0..D {
-                wk[(i, j)] = rng.sample(uniform);
-                wq[(i, j)] = rng.sample(uniform);
-            }
-            for j in 0..M {
-                wv[(i, j)] = rng.sample(uniform);
-            }
-        }
+        let dwk = SMatrix::zeros();
+        let dwq = SMatrix::zeros();
+        let dwv = SMatrix::zeros();
 
         let softmax2d = Softmax2d::new();
 
-        let optkq =
+        let optk =
+            <O as OptimizerFactory<M, D>>::Optimizer::init(
+            );
+        let optq =
             <O as OptimizerFactory<M, D>>::Optimizer::init(
             );
         let optv =
@@ -72,8 +77,12 @@ where
             v,
             z,
             s,
+            dwk,
+            dwq,
+            dwv,
             softmax2d,
-            optkq,
+            optk,
+            optq,
             optv,
         }
     }
@@ -81,6 +90,16 @@ where
     pub fn ff(
         &mut self,
         x: SMatrix<f32, N, M>,
+    ) -> SMatrix<f32, N, M> {
+        self.ff_masked(x, N)
+    }
+
+    // only the first len rows are attended to, the scores of the
+    // padded keys are -inf so they get no weight
+    pub fn ff_masked(
+        &mut self,
+        x: SMatrix<f32, N, M>,
+        len: usize,
     ) -> SMatrix<f32, N, M> {
         self.x = x;
         self.k = self.x * self.wk;
@@ -88,7 +107,10 @@ where
         self.v = self.x * self.wv;
         self.z = self.q * self.k.transpose();
         self.z = self.z / (D as f32).sqrt();
-        self.s = self.softmax2d.ff(self.s);
+        self.z
+            .columns_mut(len, N - len)
+            .fill(f32::NEG_INFINITY);
+        self.s = self.softmax2d.ff(self.z);
         self.s * self.v
     }
 
@@ -99,22 +121,83 @@ where
         let gs = &g * self.v.transpose();
         // v path
         let gv = self.s.transpose() * &g;
-        let dj_dwv = self.x.transpose() * &gv;
+        self.dwv = self.x.transpose() * &gv;
         let dj_dv = &gv * self.wv.transpose();
         // k and q path
         let gs = self.softmax2d.bp(gs);
         let gs = gs / (D as f32).sqrt();
         let gk = gs.transpose() * self.q;
         let gq = gs * self.k;
-        let dj_dwk = self.x.transpose() * &gk;
-        let dj_dwq = self.x.transpose() * &gq;
+        self.dwk = self.x.transpose() * &gk;
+        self.dwq = self.x.transpose() * &gq;
         let dj_dk = &gk * self.wk.transpose();
         let dj_dq = &gq * self.wq.transpose();
 
-        self.optkq.update_param(&mut self.wk, &dj_dwk);
-        self.optkq.update_param(&mut self.wq, &dj_dwq);
-        self.optv.update_param(&mut self.wv, &dj_dwv);
-
         dj_dk + dj_dq + dj_dv
     }
 }
+
+impl<const M: usize, const N: usize, const D: usize, O>
+    Parameters for Attention<M, N, D, O>
+where
+    O: OptimizerFactory<M, D> + OptimizerFactory<M, M>,
+{
+    fn visit_params(
+        &mut self,
+        f: &mut dyn FnMut(&mut [f32], &mut [f32]),
+    ) {
+        f(self.wk.as_mut_slice(), self.dwk.as_mut_slice());
+        f(self.wq.as_mut_slice(), self.dwq.as_mut_slice());
+        f(self.wv.as_mut_slice(), self.dwv.as_mut_slice());
+    }
+
+    fn update_params_scaled(
+        &mut self,
+        lr_scale: f32,
+        weight_decay: f32,
+    ) {
+        self.optk.update_weight(
+            &mut self.wk,
+            &self.dwk,
+            lr_scale,
+            weight_decay,
+        );
+        self.optq.update_weight(
+            &mut self.wq,
+            &self.dwq,
+            lr_scale,
+            weight_decay,
+        );
+        self.optv.update_weight(
+            &mut self.wv,
+            &self.dwv,
+            lr_scale,
+            weight_decay,
+        );
+    }
+
+    fn finish_params(&mut self) {
+        self.optk.finish(&mut self.wk);
+        self.optq.finish(&mut self.wq);
+        self.optv.finish(&mut self.wv);
+    }
+}
+
+#[test]
+fn test_attention_masks_padded_keys() {
+    use crate::optimizers::sgd::SgdFactory;
+
+    crate::rng::reseed(19);
+    let mut attention =
+        Attention::<3, 4, 2, SgdFactory<1, 1>>::new();
+    let mut x = SMatrix::<f32, 4, 3>::from_fn(|i, j| {
+        ((i * 3 + j) as f32 * 0.5).sin()
+    });
+    let y = attention.ff_masked(x, 2);
+    x.row_mut(3).fill(9.);
+    let y_padded = attention.ff_masked(x, 2);
+    // only the query of the changed row sees it
+    assert!(
+        (y.rows(0, 3) - y_padded.rows(0, 3)).norm() < 1e-6
+    );
+}
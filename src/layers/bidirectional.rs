@@ -0,0 +1,276 @@
+use std::marker::PhantomData;
+
+use nalgebra::SVector;
+
+use super::Layers;
+use super::Parameters;
+use super::Recurrent;
+
+// how the outputs of both directions are combined at every
+// timestep
+pub trait Merge<const Y: usize, const Z: usize> {
+    fn merge(
+        fwd: &SVector<f32, Y>,
+        bwd: &SVector<f32, Y>,
+    ) -> SVector<f32, Z>;
+    // gradients of both directions from the gradient of the
+    // merged output
+    fn split(
+        g: &SVector<f32, Z>,
+    ) -> (SVector<f32, Y>, SVector<f32, Y>);
+}
+
+// [fwd, bwd], Z has to be 2 * Y
+pub struct Concat;
+
+impl<const Y: usize, const Z: usize> Merge<Y, Z>
+    for Concat
+{
+    fn merge(
+        fwd: &SVector<f32, Y>,
+        bwd: &SVector<f32, Y>,
+    ) -> SVector<f32, Z> {
+        assert_eq!(Z, 2 * Y, "Concat needs Z = 2 * Y");
+        let mut out: SVector<f32, Z> = SVector::zeros();
+        out.fixed_rows_mut::<Y>(0).copy_from(fwd);
+        out.fixed_rows_mut::<Y>(Y).copy_from(bwd);
+        out
+    }
+
+    fn split(
+        g: &SVector<f32, Z>,
+    ) -> (SVector<f32, Y>, SVector<f32, Y>) {
+        (
+            g.fixed_rows::<Y>(0).into(),
+            g.fixed_rows::<Y>(Y).into(),
+        )
+    }
+}
+
+// fwd + bwd
+pub struct Sum;
+
+impl<const Y: usize> Merge<Y, Y> for Sum {
+    fn merge(
+        fwd: &SVector<f32, Y>,
+        bwd: &SVector<f32, Y>,
+    ) -> SVector<f32, Y> {
+        fwd + bwd
+    }
+
+    fn split(
+        g: &SVector<f32, Y>,
+    ) -> (SVector<f32, Y>, SVector<f32, Y>) {
+        (*g, *g)
+    }
+}
+
+// runs fwd over the sequence and bwd over the reversed sequence,
+// output t merges the states of both after having seen x[t]. with
+// a mask bwd starts from the last token instead of the padding
+pub struct Bidirectional<
+    const X: usize,
+    const Y: usize,
+    const Z: usize,
+    const T: usize,
+    R: Recurrent<X, Y, T>,
+    M: Merge<Y, Z>,
+> {
+    fwd: R,
+    bwd: R,
+    len: usize,
+    merge: PhantomData<M>,
+}
+
+impl<
+        const X: usize,
+        const Y: usize,
+        const Z: usize,
+        const T: usize,
+        R,
+        M,
+    > Bidirectional<X, Y, Z, T, R, M>
+where
+    R: Recurrent<X, Y, T>,
+    M: Merge<Y, Z>,
+{
+    pub fn new(fwd: R, bwd: R) -> Self {
+        let len = T;
+        let merge = PhantomData;
+        Self {
+            fwd,
+            bwd,
+            len,
+            merge,
+        }
+    }
+
+    // feedforward
+    pub fn ff(
+        &mut self,
+        x: [SVector<f32, X>; T],
+    ) -> [SVector<f32, Z>; T] {
+        self.ff_masked(x, T)
+    }
+
+    // feedforward over the first len timesteps
+    pub fn ff_masked(
+        &mut self,
+        x: [SVector<f32, X>; T],
+        len: usize,
+    ) -> [SVector<f32, Z>; T] {
+        self.len = len;
+        let yf = self.fwd.ff_masked(x, len);
+        let yb = self.bwd.ff_masked(reverse(x, len), len);
+        let mut out = [SVector::zeros(); T];
+        for t in 0..len {
+            out[t] = M::merge(&yf[t], &yb[len - 1 - t]);
+        }
+        out
+    }
+
+    // backprop
+    pub fn bp(
+        &mut self,
+        g: [SVector<f32, Z>; T],
+    ) -> [SVector<f32, X>; T] {
+        let len = self.len;
+        let mut gf = [SVector::zeros(); T];
+        let mut gb = [SVector::zeros(); T];
+        for t in 0..len {
+            (gf[t], gb[len - 1 - t]) = M::split(&g[t]);
+        }
+        let gxf = self.fwd.bp(gf);
+        let gxb = self.bwd.bp(gb);
+        let mut gx = [SVector::zeros(); T];
+        for t in 0..len {
+            gx[t] = gxf[t] + gxb[len - 1 - t];
+        }
+        gx
+    }
+}
+
+impl<
+        const X: usize,
+        const Y: usize,
+        const Z: usize,
+        const T: usize,
+        R,
+        M,
+    > Layers for Bidirectional<X, Y, Z, T, R, M>
+where
+    R: Recurrent<X, Y, T>,
+    M: Merge<Y, Z>,
+{
+    fn visit_layers(
+        &mut self,
+        f: &mut dyn FnMut(&str, &mut dyn Parameters),
+    ) {
+        f("forward", &mut self.fwd);
+        f("backward", &mut self.bwd);
+    }
+}
+
+impl<
+        const X: usize,
+        const Y: usize,
+        const Z: usize,
+        const T: usize,
+        R,
+        M,
+    > Recurrent<X, Z, T> for Bidirectional<X, Y, Z, T, R, M>
+where
+    R: Recurrent<X, Y, T>,
+    M: Merge<Y, Z>,
+{
+    fn ff_masked(
+        &mut self,
+        x: [SVector<f32, X>; T],
+        len: usize,
+    ) -> [SVector<f32, Z>; T] {
+        self.ff_masked(x, len)
+    }
+
+    fn reset_state(&mut self) {
+        self.fwd.reset_state();
+        self.bwd.reset_state();
+    }
+
+    fn bp(
+        &mut self,
+        gy: [SVector<f32, Z>; T],
+    ) -> [SVector<f32, X>; T] {
+        self.bp(gy)
+    }
+}
+
+// reverses the first len elements, leaving the padding after them
+fn reverse<const X: usize, const T: usize>(
+    mut x: [SVector<f32, X>; T],
+    len: usize,
+) -> [SVector<f32, X>; T] {
+    x[..len].reverse();
+    x
+}
+
+#[test]
+fn test_bidirectional_gradients() {
+    use super::assert_recurrent_gradients;
+    use super::gru::Gru;
+    use super::lstm::Lstm;
+    use crate::optimizers::sgd::SgdFactory;
+
+    crate::rng::reseed(11);
+    let mut concat =
+        Bidirectional::<2, 3, 6, 4, _, Concat>::new(
+            Lstm::<2, 3, 4, 5, SgdFactory<1, 1>>::new(),
+            Lstm::new(),
+        );
+    assert_recurrent_gradients(&mut concat);
+
+    let mut sum = Bidirectional::<2, 3, 3, 4, _, Sum>::new(
+        Gru::<2, 3, 4, 5, SgdFactory<1, 1>>::new(),
+        Gru::new(),
+    );
+    assert_recurrent_gradients(&mut sum);
+}
+
+#[test]
+fn test_bidirectional_masking() {
+    use super::gather_params;
+    use super::gru::Gru;
+    use super::scatter_params;
+    use crate::optimizers::sgd::SgdFactory;
+
+    type O = SgdFactory<1, 1>;
+
+    crate::rng::reseed(17);
+    let mut padded =
+        Bidirectional::<2, 3, 6, 5, _, Concat>::new(
+            Gru::<2, 3, 5, 5, O>::new(),
+            Gru::new(),
+        );
+    let mut short =
+        Bidirectional::<2, 3, 6, 3, _, Concat>::new(
+            Gru::<2, 3, 3, 5, O>::new(),
+            Gru::new(),
+        );
+    scatter_params(&mut short, &gather_params(&mut padded));
+
+    let x: [SVector<f32, 2>; 5] =
+        std::array::from_fn(|t| {
+            SVector::from_fn(|i, _| {
+                (t + 2 * i) as f32 * 0.3
+            })
+        });
+    let y = padded.ff_masked(x, 3);
+    let ys = short.ff([x[0], x[1], x[2]]);
+    for t in 0..3 {
+        assert!((y[t] - ys[t]).norm() < 1e-6);
+    }
+    assert_eq!(y[3].norm() + y[4].norm(), 0.);
+
+    // padding gets no gradient
+    let gx = padded.bp([SVector::repeat(1.); 5]);
+    assert_eq!(gx[3].norm() + gx[4].norm(), 0.);
+}
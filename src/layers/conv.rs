@@ -1,6 +1,8 @@
 use nalgebra::SMatrix;
-use rand::Rng;
 
+use super::Parameters;
+use crate::initializers::uniform::Uniform;
+use crate::initializers::Initializer;
 use crate::optimizers::Optimizer;
 use crate::optimizers::OptimizerFactory;
 
@@ -16,6 +18,7 @@ pub struct Conv2d<
     x: SMatrix<f32, RX, CX>,
     w: SMatrix<f32, RW, CW>,
     y: SMatrix<f32, RY, CY>,
+    dw: SMatrix<f32, RW, CW>,
     opt: <O as OptimizerFactory<RW, CW>>::Optimizer,
 }
 
@@ -32,6 +35,11 @@ where
     O: OptimizerFactory<RW, CW>,
 {
     pub fn new() -> Self {
+        Self::with_init::<Uniform<1, 2>>()
+    }
+
+    // kernel initialized by I
+    pub fn with_init<I: Initializer>() -> Self {
         assert_eq!(
             RY,
             RX - RW + 1,
@@ -43,22 +51,14 @@ where
             "Col dimensions are incorrect"
         );
 
-        let mut w = SMatrix::zeros();
+        let w = I::init(RW * CW, RW * CW);
         let x = SMatrix::zeros();
         let y = SMatrix::zeros();
-
-        // randomize w
-        let mut rng = rand::thread_rng();
-        let uniform = rand_distr::Uniform::new(-0.5, 0.5);
-        for i in 0..RW {
-            for j in 0..CW {
-                w[(i, j)] = rng.sample(uniform);
-            }
-        }
+        let dw = SMatrix::zeros();
 
         let opt = <O as OptimizerFactory<RW, CW>>::Optimizer::init();
 
-        Self { x, w, y, opt }
+        Self { x, w, y, dw, opt }
     }
 
     // feedforward
@@ -78,11 +78,46 @@ where
         &mut self,
         g: SMatrix<f32, RY, CY>,
     ) -> SMatrix<f32, RX, CX> {
-        let w_clone = self.w.clone();
-        let grad =
+        self.dw =
             conv::<RX, CX, RY, CY, RW, CW>(&self.x, &g);
-        self.opt.update_param(&mut self.w, &grad);
-        grad_conv::<RW, CW, RY, CY, RX, CX>(&w_clone, &g)
+        grad_conv::<RW, CW, RY, CY, RX, CX>(&self.w, &g)
+    }
+}
+
+impl<
+        const RX: usize,
+        const CX: usize,
+        const RY: usize,
+        const CY: usize,
+        const RW: usize,
+        const CW: usize,
+        O,
+    > Parameters for Conv2d<RX, CX, RY, CY, RW, CW, O>
+where
+    O: OptimizerFactory<RW, CW>,
+{
+    fn visit_params(
+        &mut self,
+        f: &mut dyn FnMut(&mut [f32], &mut [f32]),
+    ) {
+        f(self.w.as_mut_slice(), self.dw.as_mut_slice());
+    }
+
+    fn update_params_scaled(
+        &mut self,
+        lr_scale: f32,
+        weight_decay: f32,
+    ) {
+        self.opt.update_weight(
+            &mut self.w,
+            &self.dw,
+            lr_scale,
+            weight_decay,
+        );
+    }
+
+    fn finish_params(&mut self) {
+        self.opt.finish(&mut self.w);
     }
 }
 
@@ -0,0 +1,489 @@
+use nalgebra::SMatrix;
+use nalgebra::SVector;
+
+use super::Parameters;
+use crate::activation::deriv_all;
+use crate::activation::func_all;
+use crate::activation::tanh::Tanh;
+use crate::initializers::uniform::Uniform;
+use crate::initializers::Initializer;
+use crate::optimizers::Optimizer;
+use crate::optimizers::OptimizerFactory;
+
+// how well a decoder state d matches an encoder state e
+pub trait Score<const E: usize, const D: usize>:
+    Parameters
+{
+    fn new() -> Self;
+    fn score(
+        &self,
+        d: &SVector<f32, D>,
+        e: &SVector<f32, E>,
+    ) -> f32;
+    // adds g times the gradients of the score to the parameter
+    // gradients and returns the ones of d and e
+    fn bp(
+        &mut self,
+        g: f32,
+        d: &SVector<f32, D>,
+        e: &SVector<f32, E>,
+    ) -> (SVector<f32, D>, SVector<f32, E>);
+    fn zero_grads(&mut self);
+}
+
+// bahdanau: v . tanh(Wa e + Ua d), with K hidden units
+pub struct Additive<
+    const E: usize,
+    const D: usize,
+    const K: usize,
+    O: OptimizerFactory<K, E>
+        + OptimizerFactory<K, D>
+        + OptimizerFactory<K, 1>,
+> {
+    wa: SMatrix<f32, K, E>,
+    ua: SMatrix<f32, K, D>,
+    v: SVector<f32, K>,
+    dwa: SMatrix<f32, K, E>,
+    dua: SMatrix<f32, K, D>,
+    dv: SVector<f32, K>,
+    optwa: <O as OptimizerFactory<K, E>>::Optimizer,
+    optua: <O as OptimizerFactory<K, D>>::Optimizer,
+    optv: <O as OptimizerFactory<K, 1>>::Optimizer,
+}
+
+impl<const E: usize, const D: usize, const K: usize, O>
+    Score<E, D> for Additive<E, D, K, O>
+where
+    O: OptimizerFactory<K, E>
+        + OptimizerFactory<K, D>
+        + OptimizerFactory<K, 1>,
+{
+    fn new() -> Self {
+        let wa = Uniform::<1, 2>::init(E, K);
+        let ua = Uniform::<1, 2>::init(D, K);
+        let v = Uniform::<1, 2>::init(K, 1);
+        let dwa = SMatrix::zeros();
+        let dua = SMatrix::zeros();
+        let dv = SVector::zeros();
+        let optwa =
+            <O as OptimizerFactory<K, E>>::Optimizer::init(
+            );
+        let optua =
+            <O as OptimizerFactory<K, D>>::Optimizer::init(
+            );
+        let optv =
+            <O as OptimizerFactory<K, 1>>::Optimizer::init(
+            );
+        Self {
+            wa,
+            ua,
+            v,
+            dwa,
+            dua,
+            dv,
+            optwa,
+            optua,
+            optv,
+        }
+    }
+
+    fn score(
+        &self,
+        d: &SVector<f32, D>,
+        e: &SVector<f32, E>,
+    ) -> f32 {
+        let z = self.wa * e + self.ua * d;
+        self.v.dot(&func_all::<K, 1, Tanh>(&z))
+    }
+
+    fn bp(
+        &mut self,
+        g: f32,
+        d: &SVector<f32, D>,
+        e: &SVector<f32, E>,
+    ) -> (SVector<f32, D>, SVector<f32, E>) {
+        let z = self.wa * e + self.ua * d;
+        self.dv += g * func_all::<K, 1, Tanh>(&z);
+        let gz = deriv_all::<K, 1, Tanh>(&z)
+            .component_mul(&self.v)
+            * g;
+        self.dwa += gz * e.transpose();
+        self.dua += gz * d.transpose();
+        (self.ua.transpose() * gz, self.wa.transpose() * gz)
+    }
+
+    fn zero_grads(&mut self) {
+        self.dwa = SMatrix::zeros();
+        self.dua = SMatrix::zeros();
+        self.dv = SVector::zeros();
+    }
+}
+
+impl<const E: usize, const D: usize, const K: usize, O>
+    Parameters for Additive<E, D, K, O>
+where
+    O: OptimizerFactory<K, E>
+        + OptimizerFactory<K, D>
+        + OptimizerFactory<K, 1>,
+{
+    fn visit_params(
+        &mut self,
+        f: &mut dyn FnMut(&mut [f32], &mut [f32]),
+    ) {
+        f(self.wa.as_mut_slice(), self.dwa.as_mut_slice());
+        f(self.ua.as_mut_slice(), self.dua.as_mut_slice());
+        f(self.v.as_mut_slice(), self.dv.as_mut_slice());
+    }
+
+    fn update_params_scaled(
+        &mut self,
+        lr_scale: f32,
+        weight_decay: f32,
+    ) {
+        self.optwa.update_weight(
+            &mut self.wa,
+            &self.dwa,
+            lr_scale,
+            weight_decay,
+        );
+        self.optua.update_weight(
+            &mut self.ua,
+            &self.dua,
+            lr_scale,
+            weight_decay,
+        );
+        self.optv.update_weight(
+            &mut self.v,
+            &self.dv,
+            lr_scale,
+            weight_decay,
+        );
+    }
+
+    fn finish_params(&mut self) {
+        self.optwa.finish(&mut self.wa);
+        self.optua.finish(&mut self.ua);
+        self.optv.finish(&mut self.v);
+    }
+}
+
+// luong general: d . Wa e
+pub struct Multiplicative<
+    const E: usize,
+    const D: usize,
+    O: OptimizerFactory<D, E>,
+> {
+    wa: SMatrix<f32, D, E>,
+    dwa: SMatrix<f32, D, E>,
+    optwa: <O as OptimizerFactory<D, E>>::Optimizer,
+}
+
+impl<const E: usize, const D: usize, O> Score<E, D>
+    for Multiplicative<E, D, O>
+where
+    O: OptimizerFactory<D, E>,
+{
+    fn new() -> Self {
+        let wa = Uniform::<1, 2>::init(E, D);
+        let dwa = SMatrix::zeros();
+        let optwa =
+            <O as OptimizerFactory<D, E>>::Optimizer::init(
+            );
+        Self { wa, dwa, optwa }
+    }
+
+    fn score(
+        &self,
+        d: &SVector<f32, D>,
+        e: &SVector<f32, E>,
+    ) -> f32 {
+        d.dot(&(self.wa * e))
+    }
+
+    fn bp(
+        &mut self,
+        g: f32,
+        d: &SVector<f32, D>,
+        e: &SVector<f32, E>,
+    ) -> (SVector<f32, D>, SVector<f32, E>) {
+        self.dwa += g * d * e.transpose();
+        (g * self.wa * e, g * self.wa.transpose() * d)
+    }
+
+    fn zero_grads(&mut self) {
+        self.dwa = SMatrix::zeros();
+    }
+}
+
+impl<const E: usize, const D: usize, O> Parameters
+    for Multiplicative<E, D, O>
+where
+    O: OptimizerFactory<D, E>,
+{
+    fn visit_params(
+        &mut self,
+        f: &mut dyn FnMut(&mut [f32], &mut [f32]),
+    ) {
+        f(self.wa.as_mut_slice(), self.dwa.as_mut_slice());
+    }
+
+    fn update_params_scaled(
+        &mut self,
+        lr_scale: f32,
+        weight_decay: f32,
+    ) {
+        self.optwa.update_weight(
+            &mut self.wa,
+            &self.dwa,
+            lr_scale,
+            weight_decay,
+        );
+    }
+
+    fn finish_params(&mut self) {
+        self.optwa.finish(&mut self.wa);
+    }
+}
+
+// attention of T decoder states over the first len of S encoder
+// states, giving a context vector for every decoder state:
+// a_t = softmax_j(score(d_t, e_j)), c_t = sum_j a_tj e_j
+pub struct CrossAttention<
+    const E: usize,
+    const D: usize,
+    const S: usize,
+    const T: usize,
+    A: Score<E, D>,
+> {
+    dec: [SVector<f32, D>; T],
+    enc: [SVector<f32, E>; S],
+    len: usize,
+    a: [SVector<f32, S>; T],
+    score: A,
+}
+
+impl<
+        const E: usize,
+        const D: usize,
+        const S: usize,
+        const T: usize,
+        A,
+    > CrossAttention<E, D, S, T, A>
+where
+    A: Score<E, D>,
+{
+    pub fn new() -> Self {
+        let dec = [SVector::zeros(); T];
+        let enc = [SVector::zeros(); S];
+        let len = S;
+        let a = [SVector::zeros(); T];
+        let score = A::new();
+        Self {
+            dec,
+            enc,
+            len,
+            a,
+            score,
+        }
+    }
+
+    // feedforward
+    pub fn ff(
+        &mut self,
+        dec: [SVector<f32, D>; T],
+        enc: [SVector<f32, E>; S],
+        len: usize,
+    ) -> [SVector<f32, E>; T] {
+        self.dec = dec;
+        self.enc = enc;
+        self.len = len;
+        let mut c = [SVector::zeros(); T];
+        for t in 0..T {
+            let mut s = SVector::<f32, S>::zeros();
+            for j in 0..len {
+                s[j] = self.score.score(&dec[t], &enc[j]);
+            }
+            // softmax over the encoder states that are not
+            // padding, shifted by the max for stability
+            let max = s.rows(0, len).max();
+            self.a[t] = SVector::zeros();
+            for j in 0..len {
+                self.a[t][j] = (s[j] - max).exp();
+            }
+            self.a[t] /= self.a[t].sum();
+            for (j, e) in enc.iter().take(len).enumerate() {
+                c[t] += self.a[t][j] * e;
+            }
+        }
+        c
+    }
+
+    // backprop, gives the gradients of the decoder and the
+    // encoder states
+    pub fn bp(
+        &mut self,
+        gc: [SVector<f32, E>; T],
+    ) -> ([SVector<f32, D>; T], [SVector<f32, E>; S]) {
+        let mut gdec = [SVector::zeros(); T];
+        let mut genc = [SVector::zeros(); S];
+        self.score.zero_grads();
+        for t in 0..T {
+            let mut ga = SVector::<f32, S>::zeros();
+            for j in 0..self.len {
+                ga[j] = gc[t].dot(&self.enc[j]);
+                genc[j] += self.a[t][j] * gc[t];
+            }
+            let dot = self.a[t].dot(&ga);
+            for j in 0..self.len {
+                let gs = self.a[t][j] * (ga[j] - dot);
+                let (gd, ge) = self.score.bp(
+                    gs,
+                    &self.dec[t],
+                    &self.enc[j],
+                );
+                gdec[t] += gd;
+                genc[j] += ge;
+            }
+        }
+        (gdec, genc)
+    }
+
+    // attention weights of the last ff, row t over the encoder
+    // states
+    pub fn weights(&self) -> [SVector<f32, S>; T] {
+        self.a
+    }
+}
+
+impl<
+        const E: usize,
+        const D: usize,
+        const S: usize,
+        const T: usize,
+        A,
+    > Default for CrossAttention<E, D, S, T, A>
+where
+    A: Score<E, D>,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<
+        const E: usize,
+        const D: usize,
+        const S: usize,
+        const T: usize,
+        A,
+    > Parameters for CrossAttention<E, D, S, T, A>
+where
+    A: Score<E, D>,
+{
+    fn visit_params(
+        &mut self,
+        f: &mut dyn FnMut(&mut [f32], &mut [f32]),
+    ) {
+        self.score.visit_params(f);
+    }
+
+    fn update_params_scaled(
+        &mut self,
+        lr_scale: f32,
+        weight_decay: f32,
+    ) {
+        self.score
+            .update_params_scaled(lr_scale, weight_decay);
+    }
+
+    fn finish_params(&mut self) {
+        self.score.finish_params();
+    }
+}
+
+#[cfg(test)]
+fn check_cross_attention_gradients<A: Score<3, 2>>() {
+    use super::assert_param_gradients;
+
+    let dec: [SVector<f32, 2>; 3] =
+        std::array::from_fn(|t| {
+            SVector::from_fn(|i, _| {
+                ((t * 2 + i) as f32).sin()
+            })
+        });
+    let enc: [SVector<f32, 3>; 4] =
+        std::array::from_fn(|j| {
+            SVector::from_fn(|i, _| {
+                ((j * 3 + i) as f32).cos()
+            })
+        });
+    let r: [SVector<f32, 3>; 3] =
+        std::array::from_fn(|t| {
+            SVector::from_fn(|i, _| {
+                ((t + i) as f32 * 0.7).sin()
+            })
+        });
+    let loss =
+        |layer: &mut CrossAttention<3, 2, 4, 3, A>,
+         dec: [SVector<f32, 2>; 3],
+         enc: [SVector<f32, 3>; 4]| {
+            let c = layer.ff(dec, enc, 3);
+            (0..3).map(|t| r[t].dot(&c[t])).sum::<f32>()
+        };
+
+    let mut layer = CrossAttention::new();
+    assert_param_gradients(
+        &mut layer,
+        |layer| loss(layer, dec, enc),
+        |layer| {
+            layer.bp(r);
+        },
+    );
+
+    loss(&mut layer, dec, enc);
+    let (gdec, genc) = layer.bp(r);
+    // the padded encoder state is never attended to
+    assert_eq!(genc[3].norm(), 0.);
+    for t in 0..3 {
+        for i in 0..2 {
+            let (mut dp, mut dm) = (dec, dec);
+            dp[t][i] += 1e-2;
+            dm[t][i] -= 1e-2;
+            let fd = (loss(&mut layer, dp, enc)
+                - loss(&mut layer, dm, enc))
+                / 2e-2;
+            assert!((fd - gdec[t][i]).abs() < 2e-3);
+        }
+    }
+    for j in 0..3 {
+        for i in 0..3 {
+            let (mut ep, mut em) = (enc, enc);
+            ep[j][i] += 1e-2;
+            em[j][i] -= 1e-2;
+            let fd = (loss(&mut layer, dec, ep)
+                - loss(&mut layer, dec, em))
+                / 2e-2;
+            assert!((fd - genc[j][i]).abs() < 2e-3);
+        }
+    }
+}
+
+#[test]
+fn test_additive_attention_gradients() {
+    use crate::optimizers::sgd::SgdFactory;
+
+    crate::rng::reseed(37);
+    check_cross_attention_gradients::<
+        Additive<3, 2, 4, SgdFactory<1, 1>>,
+    >();
+}
+
+#[test]
+fn test_multiplicative_attention_gradients() {
+    use crate::optimizers::sgd::SgdFactory;
+
+    crate::rng::reseed(41);
+    check_cross_attention_gradients::<
+        Multiplicative<3, 2, SgdFactory<1, 1>>,
+    >();
+}
@@ -3,8 +3,12 @@ use nalgebra::SVector;
 use super::layernorm::LayerNorm;
 use super::sequential::Sequential;
 use super::softmax::Softmax;
+use super::Layers;
+use super::Parameters;
 use crate::activation::noact::NoActivation;
 use crate::activation::ActivationFunction;
+use crate::initializers::uniform::Uniform;
+use crate::initializers::Initializer;
 use crate::optimizers::OptimizerFactory;
 
 pub struct Dense<
@@ -43,11 +47,18 @@ where
         + OptimizerFactory<Y, 1>,
 {
     pub fn new() -> Self {
-        let start_layer = Sequential::new();
+        Self::with_init::<Uniform<1, 2>, Uniform<1, 2>>()
+    }
+
+    // weights of every sequential layer initialized by IW and
+    // biases by IB
+    pub fn with_init<IW: Initializer, IB: Initializer>(
+    ) -> Self {
+        let start_layer = Sequential::with_init::<IW, IB>();
         let mid_layers = (0..L)
-            .map(|_| Sequential::new())
+            .map(|_| Sequential::with_init::<IW, IB>())
             .collect::<Vec<_>>();
-        let final_layer = Sequential::new();
+        let final_layer = Sequential::with_init::<IW, IB>();
         let layernorm = LayerNorm::new();
         let softmax = Softmax::new();
         Self {
@@ -87,3 +98,32 @@ where
         g
     }
 }
+
+impl<
+        const X: usize,
+        const Y: usize,
+        const H: usize,
+        const L: usize,
+        F,
+        O,
+    > Layers for Dense<X, Y, H, L, F, O>
+where
+    O: OptimizerFactory<H, X>
+        + OptimizerFactory<H, 1>
+        + OptimizerFactory<H, H>
+        + OptimizerFactory<Y, H>
+        + OptimizerFactory<Y, 1>,
+{
+    fn visit_layers(
+        &mut self,
+        f: &mut dyn FnMut(&str, &mut dyn Parameters),
+    ) {
+        f("start", &mut self.start_layer);
+        for (l, layer) in
+            self.mid_layers.iter_mut().enumerate()
+        {
+            f(&format!("mid{l}"), layer);
+        }
+        f("final", &mut self.final_layer);
+    }
+}
@@ -0,0 +1,72 @@
+use nalgebra::SMatrix;
+use rand::Rng;
+
+use crate::rng::with_rng;
+
+// inverted dropout: while training every unit is zeroed with
+// probability p and the others are scaled by 1 / (1 - p), at
+// inference the layer does nothing
+pub struct Dropout<const R: usize, const C: usize> {
+    p: f32,
+    mask: SMatrix<f32, R, C>,
+    training: bool,
+}
+
+impl<const R: usize, const C: usize> Dropout<R, C> {
+    pub fn new(p: f32) -> Self {
+        let mask = SMatrix::repeat(1.);
+        let training = false;
+        Self { p, mask, training }
+    }
+
+    pub fn set_training(&mut self, training: bool) {
+        self.training = training;
+    }
+
+    // feedforward
+    pub fn ff(
+        &mut self,
+        x: SMatrix<f32, R, C>,
+    ) -> SMatrix<f32, R, C> {
+        if !self.training || self.p == 0. {
+            self.mask = SMatrix::repeat(1.);
+            return x;
+        }
+        let keep = 1. - self.p;
+        self.mask = with_rng(|rng| {
+            SMatrix::from_fn(|_, _| {
+                if rng.gen::<f32>() < keep {
+                    1. / keep
+                } else {
+                    0.
+                }
+            })
+        });
+        x.component_mul(&self.mask)
+    }
+
+    // backprop
+    pub fn bp(
+        &mut self,
+        g: SMatrix<f32, R, C>,
+    ) -> SMatrix<f32, R, C> {
+        g.component_mul(&self.mask)
+    }
+}
+
+#[test]
+fn test_dropout() {
+    crate::rng::reseed(1);
+    let mut dropout = Dropout::<100, 10>::new(0.3);
+    let x = SMatrix::repeat(2.);
+    assert_eq!(dropout.ff(x), x);
+
+    dropout.set_training(true);
+    let y = dropout.ff(x);
+    let dropped = y.iter().filter(|&&yi| yi == 0.).count();
+    assert!((dropped as f32 / 1000. - 0.3).abs() < 0.05);
+    assert!(y.iter().all(
+        |&yi| yi == 0. || (yi - 2. / 0.7).abs() < 1e-5
+    ));
+    assert_eq!(dropout.bp(x), y);
+}
@@ -11,6 +11,9 @@ use nalgebra::SVector;
 use rand::Rng;
 use rand_distr::num_traits::Zero;
 
+use super::masked::Masked;
+use crate::rng::with_rng;
+
 pub struct Embedding<const N: usize, const M: usize> {
     embeddings: Embeddings<SimpleVocab, NdArray>,
     randomemb: HashMap<String, SVector<f32, M>>,
@@ -32,53 +35,64 @@ impl<const N: usize, const M: usize> Embedding<N, M> {
         }
     }
 
+    // the first N tokens of the sentence, zero padded if it has
+    // less
     pub fn embed(
         &mut self,
         sentence: String,
-    ) -> SMatrix<f32, N, M> {
-        let part_of_speech =
-            ["NOUN", "PROPN", "ADJ", "NUM"];
-
+    ) -> Masked<N, M> {
         let mut sentence = sentence.to_lowercase();
         sentence.retain(|c| {
             c.is_alphabetic() || c.is_whitespace()
         });
         let mut out: SMatrix<f32, N, M> = SMatrix::zeros();
-        sentence
-            .split(' ')
-            .map(|token| {
+        let mut len = 0;
+        for (i, token) in
+            sentence.split(' ').take(N).enumerate()
+        {
+            out.set_row(i, &self.vector(token).transpose());
+            len = i + 1;
+        }
+        Masked::new(out, len)
+    }
 
-                if let Some(v) = self.randomemb.get(token) {
-                    return v.clone();
-                }
+    // pre-trained vector of a lowercase word, words missing from
+    // the file get a random vector which is kept for the next
+    // calls
+    pub fn vector(
+        &mut self,
+        token: &str,
+    ) -> SVector<f32, M> {
+        let part_of_speech =
+            ["NOUN", "PROPN", "ADJ", "NUM"];
+
+        if let Some(v) = self.randomemb.get(token) {
+            return v.clone();
+        }
 
-                for pos in part_of_speech {
-                    let token_pos = format!("{token}_{pos}");
-                    if let Some(v) = self.embeddings.embedding(&token_pos) {
-                        return SVector::<f32, M>::from_column_slice(
-                            &v.as_slice()
-                                .unwrap_or(&[0.])
-                                .into_iter()
-                                .map(|&x| x as f32)
-                                .collect::<Vec<_>>(),
-                        );
-                    }
-                }
-                let mut rng = rand::thread_rng();
-                let uniform = rand_distr::Uniform::new(-0.5, 0.5);
-                let mut v = SVector::zero();
-                for i in 0..M {
-                    v[i] = rng.sample(uniform);
-                }
-                self.randomemb.insert(token.to_string(), v);
-                v
-            })
-            .take(N)
-            .enumerate()
-            .for_each(|(i, x)| {
-                out.set_row(i, &x.transpose());
-            });
-        out
+        for pos in part_of_speech {
+            let token_pos = format!("{token}_{pos}");
+            if let Some(v) =
+                self.embeddings.embedding(&token_pos)
+            {
+                return SVector::<f32, M>::from_column_slice(
+                    &v.as_slice()
+                        .unwrap_or(&[0.])
+                        .into_iter()
+                        .map(|&x| x as f32)
+                        .collect::<Vec<_>>(),
+                );
+            }
+        }
+        let uniform = rand_distr::Uniform::new(-0.5, 0.5);
+        let mut v = SVector::zero();
+        with_rng(|rng| {
+            for i in 0..M {
+                v[i] = rng.sample(uniform);
+            }
+        });
+        self.randomemb.insert(token.to_string(), v);
+        v
     }
 }
 
@@ -90,7 +104,7 @@ fn test_load_embedding() {
 
     println!(
         "{}",
-        embedding.embed("Hi I ate an apple".to_string())
+        embedding.embed("Hi I ate an apple".to_string()).x
     );
 
     println!("NOT FOUND: {:?}", embedding.randomemb.keys());
@@ -0,0 +1,520 @@
+use nalgebra::SMatrix;
+use nalgebra::SVector;
+
+use super::Parameters;
+use super::Recurrent;
+use crate::activation::deriv_all;
+use crate::activation::func_all;
+use crate::activation::sigmoid::Sigmoid;
+use crate::activation::tanh::Tanh;
+use crate::initializers::uniform::Uniform;
+use crate::initializers::Initializer;
+use crate::optimizers::Optimizer;
+use crate::optimizers::OptimizerFactory;
+
+// z = sigmoid(Wz [h, x] + bz)
+// r = sigmoid(Wr [h, x] + br)
+// n = tanh(Wn [r * h, x] + bn)
+// h' = (1 - z) * n + z * h
+pub struct Gru<
+    const X: usize,
+    const H: usize,
+    const T: usize,
+    const HX: usize,
+    O: OptimizerFactory<H, HX> + OptimizerFactory<H, 1>,
+> {
+    // layer variables
+    len: usize,
+    x: [SVector<f32, X>; T],
+    h: [SVector<f32, H>; T],
+    // hidden state the window starts from and ends with, in
+    // stateful mode the next window starts where this one ended
+    stateful: bool,
+    h0: SVector<f32, H>,
+    hn: SVector<f32, H>,
+
+    // gate variables
+    h_x: [SVector<f32, HX>; T],
+    rh_x: [SVector<f32, HX>; T],
+    z: [SVector<f32, H>; T],
+    r: [SVector<f32, H>; T],
+    n: [SVector<f32, H>; T],
+
+    // pre activation variables
+    zz: [SVector<f32, H>; T],
+    zr: [SVector<f32, H>; T],
+    zn: [SVector<f32, H>; T],
+
+    // learnable params
+    wz: SMatrix<f32, H, HX>,
+    wr: SMatrix<f32, H, HX>,
+    wn: SMatrix<f32, H, HX>,
+    bz: SVector<f32, H>,
+    br: SVector<f32, H>,
+    bn: SVector<f32, H>,
+
+    // gradients
+    dwz: SMatrix<f32, H, HX>,
+    dwr: SMatrix<f32, H, HX>,
+    dwn: SMatrix<f32, H, HX>,
+    dbz: SVector<f32, H>,
+    dbr: SVector<f32, H>,
+    dbn: SVector<f32, H>,
+
+    // optimizers
+    optwz: <O as OptimizerFactory<H, HX>>::Optimizer,
+    optbz: <O as OptimizerFactory<H, 1>>::Optimizer,
+    optwr: <O as OptimizerFactory<H, HX>>::Optimizer,
+    optbr: <O as OptimizerFactory<H, 1>>::Optimizer,
+    optwn: <O as OptimizerFactory<H, HX>>::Optimizer,
+    optbn: <O as OptimizerFactory<H, 1>>::Optimizer,
+}
+
+impl<
+        const X: usize,
+        const H: usize,
+        const T: usize,
+        const HX: usize,
+        O,
+    > Gru<X, H, T, HX, O>
+where
+    O: OptimizerFactory<H, HX> + OptimizerFactory<H, 1>,
+{
+    pub fn new() -> Self {
+        Self::with_init::<
+            Uniform<1, 2>,
+            Uniform<1, 2>,
+            Uniform<1, 2>,
+        >()
+    }
+
+    // input weights initialized by IW, recurrent weights by IH
+    // and biases by IB
+    pub fn with_init<
+        IW: Initializer,
+        IH: Initializer,
+        IB: Initializer,
+    >() -> Self {
+        let len = T;
+        let x = [SVector::zeros(); T];
+        let h = [SVector::zeros(); T];
+        let stateful = false;
+        let h0 = SVector::zeros();
+        let hn = SVector::zeros();
+
+        let h_x = [SVector::zeros(); T];
+        let rh_x = [SVector::zeros(); T];
+        let z = [SVector::zeros(); T];
+        let r = [SVector::zeros(); T];
+        let n = [SVector::zeros(); T];
+
+        let zz = [SVector::zeros(); T];
+        let zr = [SVector::zeros(); T];
+        let zn = [SVector::zeros(); T];
+
+        let wz = Self::init_gate_weights::<IW, IH>();
+        let wr = Self::init_gate_weights::<IW, IH>();
+        let wn = Self::init_gate_weights::<IW, IH>();
+        let bz = IB::init(HX, H);
+        let br = IB::init(HX, H);
+        let bn = IB::init(HX, H);
+
+        let dwz = SMatrix::zeros();
+        let dwr = SMatrix::zeros();
+        let dwn = SMatrix::zeros();
+        let dbz = SVector::zeros();
+        let dbr = SVector::zeros();
+        let dbn = SVector::zeros();
+
+        let optwz =
+            <O as OptimizerFactory<H, HX>>::Optimizer::init(
+            );
+        let optbz =
+            <O as OptimizerFactory<H, 1>>::Optimizer::init_bias(
+            );
+        let optwr =
+            <O as OptimizerFactory<H, HX>>::Optimizer::init(
+            );
+        let optbr =
+            <O as OptimizerFactory<H, 1>>::Optimizer::init_bias(
+            );
+        let optwn =
+            <O as OptimizerFactory<H, HX>>::Optimizer::init(
+            );
+        let optbn =
+            <O as OptimizerFactory<H, 1>>::Optimizer::init_bias(
+            );
+
+        Self {
+            len,
+            x,
+            h,
+            stateful,
+            h0,
+            hn,
+            h_x,
+            rh_x,
+            z,
+            r,
+            n,
+            zz,
+            zr,
+            zn,
+            wz,
+            wr,
+            wn,
+            bz,
+            br,
+            bn,
+            dwz,
+            dwr,
+            dwn,
+            dbz,
+            dbr,
+            dbn,
+            optwz,
+            optbz,
+            optwr,
+            optbr,
+            optwn,
+            optbn,
+        }
+    }
+
+    // carries the state over consecutive calls of ff, bp still
+    // stops at the start of each window (truncated bptt)
+    pub fn stateful(mut self) -> Self {
+        self.stateful = true;
+        self
+    }
+
+    // starts the next window from a zero state
+    pub fn reset_state(&mut self) {
+        self.hn = SVector::zeros();
+    }
+
+    // feedforward
+    pub fn ff(
+        &mut self,
+        x: [SVector<f32, X>; T],
+    ) -> [SVector<f32, H>; T] {
+        self.ff_masked(x, T)
+    }
+
+    // feedforward over the first len timesteps
+    pub fn ff_masked(
+        &mut self,
+        x: [SVector<f32, X>; T],
+        len: usize,
+    ) -> [SVector<f32, H>; T] {
+        self.x = x;
+        self.len = len;
+        if self.stateful {
+            self.h0 = self.hn;
+        }
+        for t in 0..len {
+            let h_prev = self.h_prev(t);
+            self.h_x[t] = Self::concat(&h_prev, &self.x[t]);
+
+            self.zz[t] = self.wz * self.h_x[t] + self.bz;
+            self.z[t] =
+                func_all::<H, 1, Sigmoid>(&self.zz[t]);
+
+            self.zr[t] = self.wr * self.h_x[t] + self.br;
+            self.r[t] =
+                func_all::<H, 1, Sigmoid>(&self.zr[t]);
+
+            self.rh_x[t] = Self::concat(
+                &self.r[t].component_mul(&h_prev),
+                &self.x[t],
+            );
+            self.zn[t] = self.wn * self.rh_x[t] + self.bn;
+            self.n[t] = func_all::<H, 1, Tanh>(&self.zn[t]);
+
+            self.h[t] = (SVector::repeat(1.) - self.z[t])
+                .component_mul(&self.n[t])
+                + self.z[t].component_mul(&h_prev);
+        }
+        self.hn = self.h[len - 1];
+        for t in len..T {
+            self.h[t] = SVector::zeros();
+        }
+        self.h
+    }
+
+    // backprop
+    pub fn bp(
+        &mut self,
+        gy: [SVector<f32, H>; T],
+    ) -> [SVector<f32, X>; T] {
+        let mut gx = [SVector::zeros(); T];
+        let mut gh = SVector::zeros();
+        self.dwz = SMatrix::zeros();
+        self.dwr = SMatrix::zeros();
+        self.dwn = SMatrix::zeros();
+        self.dbz = SVector::zeros();
+        self.dbr = SVector::zeros();
+        self.dbn = SVector::zeros();
+        for t in (0..self.len).rev() {
+            let h_prev = self.h_prev(t);
+            gh += gy[t];
+
+            // h' = (1 - z) * n + z * h
+            let mut gh_prev = gh.component_mul(&self.z[t]);
+            let gz = gh
+                .component_mul(&(h_prev - self.n[t]))
+                .component_mul(
+                    &deriv_all::<H, 1, Sigmoid>(
+                        &self.zz[t],
+                    ),
+                );
+            let gn = (SVector::repeat(1.) - self.z[t])
+                .component_mul(&gh)
+                .component_mul(&deriv_all::<H, 1, Tanh>(
+                    &self.zn[t],
+                ));
+
+            // n = tanh(Wn [r * h, x] + bn)
+            self.dwn += gn * self.rh_x[t].transpose();
+            self.dbn += gn;
+            let (grh, gxn) =
+                Self::unconcat(&(self.wn.transpose() * gn));
+            gh_prev += grh.component_mul(&self.r[t]);
+            let gr =
+                grh.component_mul(&h_prev).component_mul(
+                    &deriv_all::<H, 1, Sigmoid>(
+                        &self.zr[t],
+                    ),
+                );
+
+            // z and r act on [h, x]
+            self.dwz += gz * self.h_x[t].transpose();
+            self.dbz += gz;
+            self.dwr += gr * self.h_x[t].transpose();
+            self.dbr += gr;
+            let ghx = self.wz.transpose() * gz
+                + self.wr.transpose() * gr;
+            let (ghzr, gxzr) = Self::unconcat(&ghx);
+
+            gx[t] = gxn + gxzr;
+            gh = gh_prev + ghzr;
+        }
+
+        gx
+    }
+
+    fn h_prev(&self, t: usize) -> SVector<f32, H> {
+        if t != 0 {
+            self.h[t - 1]
+        } else {
+            self.h0
+        }
+    }
+
+    // the gate weights act on [h, x], so the recurrent and the
+    // input blocks are initialized separately
+    fn init_gate_weights<
+        IW: Initializer,
+        IH: Initializer,
+    >() -> SMatrix<f32, H, HX> {
+        let mut w: SMatrix<f32, H, HX> = SMatrix::zeros();
+        w.fixed_view_mut::<H, H>(0, 0)
+            .copy_from(&IH::init::<H, H>(H, H));
+        w.fixed_view_mut::<H, X>(0, H)
+            .copy_from(&IW::init::<H, X>(X, H));
+        w
+    }
+
+    fn concat(
+        h: &SVector<f32, H>,
+        x: &SVector<f32, X>,
+    ) -> SVector<f32, HX> {
+        let mut out = SVector::zeros();
+        for i in 0..H {
+            out[i] = h[i];
+        }
+        for i in 0..X {
+            out[H + i] = x[i];
+        }
+        out
+    }
+
+    fn unconcat(
+        hx: &SVector<f32, HX>,
+    ) -> (SVector<f32, H>, SVector<f32, X>) {
+        let mut h = SVector::zeros();
+        let mut x = SVector::zeros();
+        for i in 0..H {
+            h[i] = hx[i];
+        }
+        for i in 0..X {
+            x[i] = hx[H + i];
+        }
+        (h, x)
+    }
+}
+
+impl<
+        const X: usize,
+        const H: usize,
+        const T: usize,
+        const HX: usize,
+        O,
+    > Default for Gru<X, H, T, HX, O>
+where
+    O: OptimizerFactory<H, HX> + OptimizerFactory<H, 1>,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<
+        const X: usize,
+        const H: usize,
+        const T: usize,
+        const HX: usize,
+        O,
+    > Recurrent<X, H, T> for Gru<X, H, T, HX, O>
+where
+    O: OptimizerFactory<H, HX> + OptimizerFactory<H, 1>,
+{
+    fn ff_masked(
+        &mut self,
+        x: [SVector<f32, X>; T],
+        len: usize,
+    ) -> [SVector<f32, H>; T] {
+        self.ff_masked(x, len)
+    }
+
+    fn reset_state(&mut self) {
+        self.reset_state()
+    }
+
+    fn is_stateful(&self) -> bool {
+        self.stateful
+    }
+
+    fn bp(
+        &mut self,
+        gy: [SVector<f32, H>; T],
+    ) -> [SVector<f32, X>; T] {
+        self.bp(gy)
+    }
+}
+
+impl<
+        const X: usize,
+        const H: usize,
+        const T: usize,
+        const HX: usize,
+        O,
+    > Parameters for Gru<X, H, T, HX, O>
+where
+    O: OptimizerFactory<H, HX> + OptimizerFactory<H, 1>,
+{
+    fn visit_params(
+        &mut self,
+        f: &mut dyn FnMut(&mut [f32], &mut [f32]),
+    ) {
+        f(self.wz.as_mut_slice(), self.dwz.as_mut_slice());
+        f(self.bz.as_mut_slice(), self.dbz.as_mut_slice());
+        f(self.wr.as_mut_slice(), self.dwr.as_mut_slice());
+        f(self.br.as_mut_slice(), self.dbr.as_mut_slice());
+        f(self.wn.as_mut_slice(), self.dwn.as_mut_slice());
+        f(self.bn.as_mut_slice(), self.dbn.as_mut_slice());
+    }
+
+    fn update_params_scaled(
+        &mut self,
+        lr_scale: f32,
+        weight_decay: f32,
+    ) {
+        self.optwz.update_weight(
+            &mut self.wz,
+            &self.dwz,
+            lr_scale,
+            weight_decay,
+        );
+        self.optbz.update_param_scaled(
+            &mut self.bz,
+            &self.dbz,
+            lr_scale,
+        );
+        self.optwr.update_weight(
+            &mut self.wr,
+            &self.dwr,
+            lr_scale,
+            weight_decay,
+        );
+        self.optbr.update_param_scaled(
+            &mut self.br,
+            &self.dbr,
+            lr_scale,
+        );
+        self.optwn.update_weight(
+            &mut self.wn,
+            &self.dwn,
+            lr_scale,
+            weight_decay,
+        );
+        self.optbn.update_param_scaled(
+            &mut self.bn,
+            &self.dbn,
+            lr_scale,
+        );
+    }
+
+    fn finish_params(&mut self) {
+        self.optwz.finish(&mut self.wz);
+        self.optbz.finish(&mut self.bz);
+        self.optwr.finish(&mut self.wr);
+        self.optbr.finish(&mut self.br);
+        self.optwn.finish(&mut self.wn);
+        self.optbn.finish(&mut self.bn);
+    }
+}
+
+#[test]
+fn test_gru_gradients() {
+    use super::assert_recurrent_gradients;
+    use crate::optimizers::sgd::SgdFactory;
+
+    crate::rng::reseed(3);
+    let mut layer =
+        Gru::<2, 3, 4, 5, SgdFactory<1, 1>>::new();
+    assert_recurrent_gradients(&mut layer);
+}
+
+#[test]
+fn test_stateful_gru() {
+    use super::gather_params;
+    use super::scatter_params;
+    use crate::optimizers::sgd::SgdFactory;
+
+    type O = SgdFactory<1, 1>;
+
+    crate::rng::reseed(37);
+    let mut full = Gru::<2, 3, 4, 5, O>::new();
+    let mut window = Gru::<2, 3, 2, 5, O>::new().stateful();
+    scatter_params(&mut window, &gather_params(&mut full));
+
+    let x: [SVector<f32, 2>; 4] =
+        std::array::from_fn(|t| {
+            SVector::from_fn(|i, _| {
+                ((t * 2 + i) as f32).sin()
+            })
+        });
+    let y = full.ff(x);
+    // two consecutive windows see the same states as one pass
+    let y0 = window.ff([x[0], x[1]]);
+    let y1 = window.ff([x[2], x[3]]);
+    for t in 0..2 {
+        assert!((y[t] - y0[t]).norm() < 1e-6);
+        assert!((y[t + 2] - y1[t]).norm() < 1e-6);
+    }
+
+    window.reset_state();
+    let y0 = window.ff([x[0], x[1]]);
+    assert!((y[1] - y0[1]).norm() < 1e-6);
+}
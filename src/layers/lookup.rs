@@ -0,0 +1,192 @@
+use nalgebra::SMatrix;
+use nalgebra::SVector;
+
+use super::embedding::Embedding;
+use super::masked::Masked;
+use super::Parameters;
+use crate::initializers::uniform::Uniform;
+use crate::initializers::Initializer;
+use crate::optimizers::Optimizer;
+use crate::optimizers::OptimizerFactory;
+
+// trainable embedding table, row i is the vector of token i. only
+// the rows read by the last sequence get gradients, and only those
+// rows are updated (each row has its own optimizer, created the
+// first time the row is updated)
+pub struct Lookup<
+    const N: usize,
+    const M: usize,
+    O: OptimizerFactory<M, 1>,
+> {
+    ids: [usize; N],
+    len: usize,
+    w: Vec<SVector<f32, M>>,
+    dw: Vec<SVector<f32, M>>,
+    // rows with a gradient from the last bp
+    rows: Vec<usize>,
+    opts: Vec<
+        Option<<O as OptimizerFactory<M, 1>>::Optimizer>,
+    >,
+}
+
+impl<const N: usize, const M: usize, O> Lookup<N, M, O>
+where
+    O: OptimizerFactory<M, 1>,
+{
+    pub fn new(vocab: usize) -> Self {
+        Self::with_init::<Uniform<1, 2>>(vocab)
+    }
+
+    // every row initialized by I
+    pub fn with_init<I: Initializer>(vocab: usize) -> Self {
+        let w =
+            (0..vocab).map(|_| I::init(vocab, M)).collect();
+        Self::from_rows(w)
+    }
+
+    // starts from the pre-trained vectors of the words, token i
+    // being words[i]
+    pub fn from_embedding<const K: usize>(
+        embedding: &mut Embedding<K, M>,
+        words: &[String],
+    ) -> Self {
+        let w = words
+            .iter()
+            .map(|word| {
+                embedding.vector(&word.to_lowercase())
+            })
+            .collect();
+        Self::from_rows(w)
+    }
+
+    pub fn from_rows(w: Vec<SVector<f32, M>>) -> Self {
+        let ids = [0; N];
+        let len = 0;
+        let dw = vec![SVector::zeros(); w.len()];
+        let rows = Vec::new();
+        let opts = w.iter().map(|_| None).collect();
+        Self {
+            ids,
+            len,
+            w,
+            dw,
+            rows,
+            opts,
+        }
+    }
+
+    pub fn vocab(&self) -> usize {
+        self.w.len()
+    }
+
+    // feedforward, the first N tokens and zero padding after them
+    pub fn ff(&mut self, tokens: &[usize]) -> Masked<N, M> {
+        self.len = tokens.len().min(N);
+        let mut out: SMatrix<f32, N, M> = SMatrix::zeros();
+        for (i, &id) in tokens.iter().take(N).enumerate() {
+            self.ids[i] = id;
+            out.set_row(i, &self.w[id].transpose());
+        }
+        Masked::new(out, self.len)
+    }
+
+    // backprop, a token appearing several times gets the sum of
+    // its gradients
+    pub fn bp(&mut self, g: [SVector<f32, M>; N]) {
+        for &row in &self.rows {
+            self.dw[row] = SVector::zeros();
+        }
+        self.rows = self.ids[..self.len].to_vec();
+        self.rows.sort_unstable();
+        self.rows.dedup();
+        for (&id, gi) in self.ids[..self.len].iter().zip(g)
+        {
+            self.dw[id] += gi;
+        }
+    }
+}
+
+impl<const N: usize, const M: usize, O> Parameters
+    for Lookup<N, M, O>
+where
+    O: OptimizerFactory<M, 1>,
+{
+    // every row and not only the ones of the last bp: ema,
+    // gather_params and scatter_params match the parameters by
+    // position from one call to the next. the other rows have zero
+    // gradients so clipping is unaffected
+    fn visit_params(
+        &mut self,
+        f: &mut dyn FnMut(&mut [f32], &mut [f32]),
+    ) {
+        for (w, dw) in self.w.iter_mut().zip(&mut self.dw) {
+            f(w.as_mut_slice(), dw.as_mut_slice());
+        }
+    }
+
+    fn update_params_scaled(
+        &mut self,
+        lr_scale: f32,
+        weight_decay: f32,
+    ) {
+        for &row in &self.rows {
+            self.opts[row]
+                .get_or_insert_with(|| {
+                    <O as OptimizerFactory<M, 1>>::Optimizer::init()
+                })
+                .update_weight(
+                    &mut self.w[row],
+                    &self.dw[row],
+                    lr_scale,
+                    weight_decay,
+                );
+        }
+    }
+
+    fn finish_params(&mut self) {
+        for (w, opt) in
+            self.w.iter_mut().zip(&mut self.opts)
+        {
+            if let Some(opt) = opt {
+                opt.finish(w);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_lookup_sparse_update() {
+    use crate::optimizers::sgd::SgdFactory;
+
+    crate::rng::reseed(5);
+    let mut lookup =
+        Lookup::<4, 3, SgdFactory<1, 10>>::new(5);
+    let before = lookup.w.clone();
+    let x = lookup.ff(&[2, 0, 2]);
+    assert_eq!(x.len, 3);
+    assert_eq!(x.x.row(2).transpose(), before[2]);
+    assert_eq!(x.x.row(3).norm(), 0.);
+
+    let g: [SVector<f32, 3>; 4] =
+        std::array::from_fn(|t| {
+            SVector::from_fn(|i, _| (t * 3 + i) as f32)
+        });
+    lookup.bp(g);
+    assert_eq!(lookup.dw[2], g[0] + g[2]);
+    assert_eq!(lookup.dw[0], g[1]);
+    lookup.update_params();
+    for (row, (w, b)) in
+        lookup.w.iter().zip(&before).enumerate()
+    {
+        assert_eq!(w == b, row != 0 && row != 2);
+    }
+
+    // the next bp forgets the rows of the previous one
+    lookup.ff(&[1]);
+    lookup.bp(g);
+    assert_eq!(
+        lookup.dw[2].norm() + lookup.dw[0].norm(),
+        0.
+    );
+    assert_eq!(lookup.dw[1], g[0]);
+}
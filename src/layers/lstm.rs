@@ -1,25 +1,49 @@
 use nalgebra::SMatrix;
 use nalgebra::SVector;
-use rand::Rng;
 
+use super::Parameters;
+use super::Recurrent;
 use crate::activation::deriv_all;
 use crate::activation::func_all;
 use crate::activation::sigmoid::Sigmoid;
 use crate::activation::tanh::Tanh;
+use crate::initializers::constant::Constant;
+use crate::initializers::uniform::Uniform;
+use crate::initializers::Initializer;
 use crate::optimizers::Optimizer;
 use crate::optimizers::OptimizerFactory;
 
+// f = sigmoid(Wf [h, x] + bf)
+// i = sigmoid(Wi [h, x] + bi)
+// c_bar = tanh(Wc [h, x] + bc)
+// c' = f * c + i * c_bar
+// o = sigmoid(Wo [h, x] + bo)
+// h' = o * tanh(c')
+// with PEEPHOLE the gates also see the cell state, through
+// pf * c and pi * c for f and i and po * c' for o
 pub struct Lstm<
     const X: usize,
     const H: usize,
     const T: usize,
     const HX: usize,
     O: OptimizerFactory<H, HX> + OptimizerFactory<H, 1>,
+    const PEEPHOLE: bool = false,
 > {
     // layer variables
+    len: usize,
     x: [SVector<f32, X>; T],
     h: [SVector<f32, H>; T],
     c: [SVector<f32, H>; T],
+    // hidden and cell states the window starts from and ends with, in
+    // stateful mode the next window starts where this one ended
+    stateful: bool,
+    h0: SVector<f32, H>,
+    hn: SVector<f32, H>,
+    c0: SVector<f32, H>,
+    cn: SVector<f32, H>,
+    // gradients of h0 and c0 from the last bp
+    gh0: SVector<f32, H>,
+    gc0: SVector<f32, H>,
 
     // gate variables
     h_x: [SVector<f32, HX>; T],
@@ -44,6 +68,22 @@ pub struct Lstm<
     bi: SVector<f32, H>,
     bc: SVector<f32, H>,
     bo: SVector<f32, H>,
+    pf: SVector<f32, H>,
+    pi: SVector<f32, H>,
+    po: SVector<f32, H>,
+
+    // gradients
+    dwf: SMatrix<f32, H, HX>,
+    dwi: SMatrix<f32, H, HX>,
+    dwc: SMatrix<f32, H, HX>,
+    dwo: SMatrix<f32, H, HX>,
+    dbf: SVector<f32, H>,
+    dbi: SVector<f32, H>,
+    dbc: SVector<f32, H>,
+    dbo: SVector<f32, H>,
+    dpf: SVector<f32, H>,
+    dpi: SVector<f32, H>,
+    dpo: SVector<f32, H>,
 
     // optimizers
     optwf: <O as OptimizerFactory<H, HX>>::Optimizer,
@@ -54,6 +94,9 @@ pub struct Lstm<
     optbc: <O as OptimizerFactory<H, 1>>::Optimizer,
     optwo: <O as OptimizerFactory<H, HX>>::Optimizer,
     optbo: <O as OptimizerFactory<H, 1>>::Optimizer,
+    optpf: <O as OptimizerFactory<H, 1>>::Optimizer,
+    optpi: <O as OptimizerFactory<H, 1>>::Optimizer,
+    optpo: <O as OptimizerFactory<H, 1>>::Optimizer,
 }
 
 impl<
@@ -62,14 +105,42 @@ impl<
         const T: usize,
         const HX: usize,
         O,
-    > Lstm<X, H, T, HX, O>
+        const PEEPHOLE: bool,
+    > Lstm<X, H, T, HX, O, PEEPHOLE>
 where
     O: OptimizerFactory<H, HX> + OptimizerFactory<H, 1>,
 {
+    // forget gate biases start at 1 so that the cell keeps its
+    // state early in training
     pub fn new() -> Self {
+        Self::with_init::<
+            Uniform<1, 2>,
+            Uniform<1, 2>,
+            Uniform<1, 2>,
+            Constant<1, 1>,
+        >()
+    }
+
+    // input weights initialized by IW, recurrent weights by IH,
+    // biases by IB except the forget gate bias which uses IF
+    // (Constant<1, 1> is the usual choice), peepholes start at 0
+    pub fn with_init<
+        IW: Initializer,
+        IH: Initializer,
+        IB: Initializer,
+        IF: Initializer,
+    >() -> Self {
+        let len = T;
         let x = [SVector::zeros(); T];
         let h = [SVector::zeros(); T];
         let c = [SVector::zeros(); T];
+        let stateful = false;
+        let h0 = SVector::zeros();
+        let hn = SVector::zeros();
+        let c0 = SVector::zeros();
+        let cn = SVector::zeros();
+        let gh0 = SVector::zeros();
+        let gc0 = SVector::zeros();
 
         let h_x = [SVector::zeros(); T];
         let f = [SVector::zeros(); T];
@@ -83,60 +154,76 @@ where
         let zc = [SVector::zeros(); T];
         let zo = [SVector::zeros(); T];
 
-        let mut wf = SMatrix::zeros();
-        let mut wi = SMatrix::zeros();
-        let mut wc = SMatrix::zeros();
-        let mut wo = SMatrix::zeros();
-        let mut bf = SVector::zeros();
-        let mut bi = SVector::zeros();
-        let mut bc = SVector::zeros();
-        let mut bo = SVector::zeros();
+        let wf = Self::init_gate_weights::<IW, IH>();
+        let wi = Self::init_gate_weights::<IW, IH>();
+        let wc = Self::init_gate_weights::<IW, IH>();
+        let wo = Self::init_gate_weights::<IW, IH>();
+        let bf = IF::init(HX, H);
+        let bi = IB::init(HX, H);
+        let bc = IB::init(HX, H);
+        let bo = IB::init(HX, H);
+        let pf = SVector::zeros();
+        let pi = SVector::zeros();
+        let po = SVector::zeros();
 
-        let mut rng = rand::thread_rng();
-        let uniform = rand_distr::Uniform::new(-0.5, 0.5);
-
-        for i in 0..H {
-            for j in 0..HX {
-                wf[(i, j)] = rng.sample(uniform);
-                wi[(i, j)] = rng.sample(uniform);
-                wc[(i, j)] = rng.sample(uniform);
-                wo[(i, j)] = rng.sample(uniform);
-            }
-            bf[i] = rng.sample(uniform);
-            bi[i] = rng.sample(uniform);
-            bc[i] = rng.sample(uniform);
-            bo[i] = rng.sample(uniform);
-        }
+        let dwf = SMatrix::zeros();
+        let dwi = SMatrix::zeros();
+        let dwc = SMatrix::zeros();
+        let dwo = SMatrix::zeros();
+        let dbf = SVector::zeros();
+        let dbi = SVector::zeros();
+        let dbc = SVector::zeros();
+        let dbo = SVector::zeros();
+        let dpf = SVector::zeros();
+        let dpi = SVector::zeros();
+        let dpo = SVector::zeros();
 
         let optwf =
             <O as OptimizerFactory<H, HX>>::Optimizer::init(
             );
         let optbf =
-            <O as OptimizerFactory<H, 1>>::Optimizer::init(
+            <O as OptimizerFactory<H, 1>>::Optimizer::init_bias(
             );
         let optwi =
             <O as OptimizerFactory<H, HX>>::Optimizer::init(
             );
         let optbi =
-            <O as OptimizerFactory<H, 1>>::Optimizer::init(
+            <O as OptimizerFactory<H, 1>>::Optimizer::init_bias(
             );
         let optwc =
             <O as OptimizerFactory<H, HX>>::Optimizer::init(
             );
         let optbc =
-            <O as OptimizerFactory<H, 1>>::Optimizer::init(
+            <O as OptimizerFactory<H, 1>>::Optimizer::init_bias(
             );
         let optwo =
             <O as OptimizerFactory<H, HX>>::Optimizer::init(
             );
         let optbo =
+            <O as OptimizerFactory<H, 1>>::Optimizer::init_bias(
+            );
+        let optpf =
+            <O as OptimizerFactory<H, 1>>::Optimizer::init(
+            );
+        let optpi =
+            <O as OptimizerFactory<H, 1>>::Optimizer::init(
+            );
+        let optpo =
             <O as OptimizerFactory<H, 1>>::Optimizer::init(
             );
 
         Self {
+            len,
             x,
             h,
             c,
+            stateful,
+            h0,
+            hn,
+            c0,
+            cn,
+            gh0,
+            gc0,
             h_x,
             f,
             i,
@@ -155,6 +242,20 @@ where
             bi,
             bc,
             bo,
+            pf,
+            pi,
+            po,
+            dwf,
+            dwi,
+            dwc,
+            dwo,
+            dbf,
+            dbi,
+            dbc,
+            dbo,
+            dpf,
+            dpi,
+            dpo,
             optwf,
             optbf,
             optwi,
@@ -163,50 +264,114 @@ where
             optbc,
             optwo,
             optbo,
+            optpf,
+            optpi,
+            optpo,
         }
     }
 
+    // carries the state over consecutive calls of ff, bp still
+    // stops at the start of each window (truncated bptt)
+    pub fn stateful(mut self) -> Self {
+        self.stateful = true;
+        self
+    }
+
+    // starts the next window from a zero state
+    pub fn reset_state(&mut self) {
+        self.hn = SVector::zeros();
+        self.cn = SVector::zeros();
+    }
+
+    // the next windows start from h0, c0 instead of zeros, e.g.
+    // a decoder starting from the final state of an encoder
+    pub fn set_initial_state(
+        &mut self,
+        h0: SVector<f32, H>,
+        c0: SVector<f32, H>,
+    ) {
+        assert!(!self.stateful);
+        self.h0 = h0;
+        self.c0 = c0;
+    }
+
+    // hidden and cell states after the last timestep of ff
+    pub fn final_state(
+        &self,
+    ) -> (SVector<f32, H>, SVector<f32, H>) {
+        (self.hn, self.cn)
+    }
+
+    // gradients of the initial hidden and cell states from the
+    // last bp
+    pub fn initial_state_grad(
+        &self,
+    ) -> (SVector<f32, H>, SVector<f32, H>) {
+        (self.gh0, self.gc0)
+    }
+
     // feedforward
     pub fn ff(
         &mut self,
         x: [SVector<f32, X>; T],
+    ) -> [SVector<f32, H>; T] {
+        self.ff_masked(x, T)
+    }
+
+    // feedforward over the first len timesteps
+    pub fn ff_masked(
+        &mut self,
+        x: [SVector<f32, X>; T],
+        len: usize,
     ) -> [SVector<f32, H>; T] {
         self.x = x;
-        for t in 0..T {
-            self.h_x[t] = if t != 0 {
-                Self::concat(&self.h[t - 1], &self.x[t])
-            } else {
-                Self::concat(&SVector::zeros(), &self.x[t])
-            };
+        self.len = len;
+        if self.stateful {
+            self.h0 = self.hn;
+            self.c0 = self.cn;
+        }
+        for t in 0..len {
+            let h_prev = self.h_prev(t);
+            let c_prev = self.c_prev(t);
+            self.h_x[t] = Self::concat(&h_prev, &self.x[t]);
 
             self.zf[t] = self.wf * self.h_x[t] + self.bf;
+            self.zi[t] = self.wi * self.h_x[t] + self.bi;
+            if PEEPHOLE {
+                self.zf[t] +=
+                    self.pf.component_mul(&c_prev);
+                self.zi[t] +=
+                    self.pi.component_mul(&c_prev);
+            }
             self.f[t] =
                 func_all::<H, 1, Sigmoid>(&self.zf[t]);
-
-            self.zi[t] = self.wi * self.h_x[t] + self.bi;
-            self.i[t] = func_all::<H, 1, Tanh>(&self.zi[t]);
+            self.i[t] =
+                func_all::<H, 1, Sigmoid>(&self.zi[t]);
 
             self.zc[t] = self.wc * self.h_x[t] + self.bc;
             self.c_bar[t] =
-                func_all::<H, 1, Sigmoid>(&self.zc[t]);
+                func_all::<H, 1, Tanh>(&self.zc[t]);
+
+            self.c[t] = self.f[t].component_mul(&c_prev)
+                + self.i[t].component_mul(&self.c_bar[t]);
+            self.ch[t] = func_all::<H, 1, Tanh>(&self.c[t]);
 
             self.zo[t] = self.wo * self.h_x[t] + self.bo;
+            if PEEPHOLE {
+                self.zo[t] +=
+                    self.po.component_mul(&self.c[t]);
+            }
             self.o[t] =
                 func_all::<H, 1, Sigmoid>(&self.zo[t]);
 
-            let c1 = if t != 0 {
-                self.c[t - 1].component_mul(&self.f[t])
-            } else {
-                SVector::zeros()
-            };
-            let c2 =
-                self.i[t].component_mul(&self.c_bar[t]);
-            self.c[t] = c1 + c2;
-            self.ch[t] = func_all::<H, 1, Tanh>(&self.c[t]);
-
             self.h[t] =
                 self.o[t].component_mul(&self.ch[t]);
         }
+        self.hn = self.h[len - 1];
+        self.cn = self.c[len - 1];
+        for t in len..T {
+            self.h[t] = SVector::zeros();
+        }
         self.h
     }
 
@@ -214,21 +379,41 @@ where
     pub fn bp(
         &mut self,
         gy: [SVector<f32, H>; T],
+    ) -> [SVector<f32, X>; T] {
+        self.bp_with_state(
+            gy,
+            SVector::zeros(),
+            SVector::zeros(),
+        )
+    }
+
+    // backprop with ghn and gcn the gradients of the final hidden
+    // and cell states, from whatever started from them
+    pub fn bp_with_state(
+        &mut self,
+        gy: [SVector<f32, H>; T],
+        ghn: SVector<f32, H>,
+        gcn: SVector<f32, H>,
     ) -> [SVector<f32, X>; T] {
         let mut gx = [SVector::zeros(); T];
-        let mut gc = SVector::zeros();
-        let mut gh = SVector::zeros();
-        let mut dwf = SMatrix::zeros();
-        let mut dwi = SMatrix::zeros();
-        let mut dwc = SMatrix::zeros();
-        let mut dwo = SMatrix::zeros();
-        let mut dbf = SVector::zeros();
-        let mut dbi = SVector::zeros();
-        let mut dbc = SVector::zeros();
-        let mut dbo = SVector::zeros();
-        for t in (0..T).rev() {
-            let mut ghx = SVector::zeros();
-            gh = gh + &gy[t];
+        let mut gc = gcn;
+        let mut gh = ghn;
+        self.dwf = SMatrix::zeros();
+        self.dwi = SMatrix::zeros();
+        self.dwc = SMatrix::zeros();
+        self.dwo = SMatrix::zeros();
+        self.dbf = SVector::zeros();
+        self.dbi = SVector::zeros();
+        self.dbc = SVector::zeros();
+        self.dbo = SVector::zeros();
+        self.dpf = SVector::zeros();
+        self.dpi = SVector::zeros();
+        self.dpo = SVector::zeros();
+        for t in (0..self.len).rev() {
+            let c_prev = self.c_prev(t);
+            gh += gy[t];
+
+            // h' = o * tanh(c')
             let go = gh
                 .component_mul(&self.ch[t])
                 .component_mul(
@@ -236,63 +421,95 @@ where
                         &self.zo[t],
                     ),
                 );
-            dwo += &go * self.h_x[t].transpose();
-            dbo += &go;
-            ghx += self.wo.transpose() * go;
-            gc = gc + gh.component_mul(&self.o[t]);
-            gc = gc.component_mul(
-                &deriv_all::<H, 1, Tanh>(&self.c[t]),
-            );
+            gc +=
+                gh.component_mul(&self.o[t]).component_mul(
+                    &deriv_all::<H, 1, Tanh>(&self.c[t]),
+                );
+            if PEEPHOLE {
+                gc += go.component_mul(&self.po);
+                self.dpo += go.component_mul(&self.c[t]);
+            }
+
+            // c' = f * c + i * c_bar
+            let gf =
+                gc.component_mul(&c_prev).component_mul(
+                    &deriv_all::<H, 1, Sigmoid>(
+                        &self.zf[t],
+                    ),
+                );
             let gi = gc
                 .component_mul(&self.c_bar[t])
-                .component_mul(&deriv_all::<H, 1, Tanh>(
-                    &self.zi[t],
-                ));
-            dwi += &gi * self.h_x[t].transpose();
-            dbi += &gi;
-            ghx += self.wi.transpose() * gi;
-            let gcbar =
-                gc.component_mul(&self.i[t]).component_mul(
+                .component_mul(
                     &deriv_all::<H, 1, Sigmoid>(
-                        &self.zc[t],
+                        &self.zi[t],
                     ),
                 );
-            dwc += &gcbar * self.h_x[t].transpose();
-            dbc += gcbar;
-            ghx += self.wc.transpose() * gcbar;
-            let gf = if t != 0 {
-                gc.component_mul(&self.c[t - 1])
-                    .component_mul(&deriv_all::<
-                        H,
-                        1,
-                        Sigmoid,
-                    >(
-                        &self.zf[t]
-                    ))
-            } else {
-                SVector::zeros()
-            };
-            dwf += &gf * self.h_x[t].transpose();
-            dbf += &gf;
-            ghx += self.wf.transpose() * gf;
+            let gcbar =
+                gc.component_mul(&self.i[t]).component_mul(
+                    &deriv_all::<H, 1, Tanh>(&self.zc[t]),
+                );
+
+            self.dwf += gf * self.h_x[t].transpose();
+            self.dbf += gf;
+            self.dwi += gi * self.h_x[t].transpose();
+            self.dbi += gi;
+            self.dwc += gcbar * self.h_x[t].transpose();
+            self.dbc += gcbar;
+            self.dwo += go * self.h_x[t].transpose();
+            self.dbo += go;
+            let ghx = self.wf.transpose() * gf
+                + self.wi.transpose() * gi
+                + self.wc.transpose() * gcbar
+                + self.wo.transpose() * go;
+
             gc = gc.component_mul(&self.f[t]);
+            if PEEPHOLE {
+                gc += gf.component_mul(&self.pf)
+                    + gi.component_mul(&self.pi);
+                self.dpf += gf.component_mul(&c_prev);
+                self.dpi += gi.component_mul(&c_prev);
+            }
             let (tmp_gh, tmp_gx) = Self::unconcat(&ghx);
             gx[t] = tmp_gx;
             gh = tmp_gh;
         }
-
-        self.optwf.update_param(&mut self.wf, &dwf);
-        self.optbf.update_param(&mut self.bf, &dbf);
-        self.optwi.update_param(&mut self.wi, &dwi);
-        self.optbi.update_param(&mut self.bi, &dbi);
-        self.optwc.update_param(&mut self.wc, &dwc);
-        self.optbc.update_param(&mut self.bc, &dbc);
-        self.optwo.update_param(&mut self.wo, &dwo);
-        self.optbo.update_param(&mut self.bo, &dbo);
+        self.gh0 = gh;
+        self.gc0 = gc;
 
         gx
     }
 
+    // the state before the first timestep is h0, c0
+    fn h_prev(&self, t: usize) -> SVector<f32, H> {
+        if t != 0 {
+            self.h[t - 1]
+        } else {
+            self.h0
+        }
+    }
+
+    fn c_prev(&self, t: usize) -> SVector<f32, H> {
+        if t != 0 {
+            self.c[t - 1]
+        } else {
+            self.c0
+        }
+    }
+
+    // the gate weights act on [h, x], so the recurrent and the
+    // input blocks are initialized separately
+    fn init_gate_weights<
+        IW: Initializer,
+        IH: Initializer,
+    >() -> SMatrix<f32, H, HX> {
+        let mut w: SMatrix<f32, H, HX> = SMatrix::zeros();
+        w.fixed_view_mut::<H, H>(0, 0)
+            .copy_from(&IH::init::<H, H>(H, H));
+        w.fixed_view_mut::<H, X>(0, H)
+            .copy_from(&IW::init::<H, X>(X, H));
+        w
+    }
+
     fn concat(
         h: &SVector<f32, H>,
         x: &SVector<f32, X>,
@@ -321,3 +538,296 @@ where
         (h, x)
     }
 }
+
+impl<
+        const X: usize,
+        const H: usize,
+        const T: usize,
+        const HX: usize,
+        O,
+        const PEEPHOLE: bool,
+    > Recurrent<X, H, T> for Lstm<X, H, T, HX, O, PEEPHOLE>
+where
+    O: OptimizerFactory<H, HX> + OptimizerFactory<H, 1>,
+{
+    fn ff_masked(
+        &mut self,
+        x: [SVector<f32, X>; T],
+        len: usize,
+    ) -> [SVector<f32, H>; T] {
+        self.ff_masked(x, len)
+    }
+
+    fn reset_state(&mut self) {
+        self.reset_state()
+    }
+
+    fn is_stateful(&self) -> bool {
+        self.stateful
+    }
+
+    fn bp(
+        &mut self,
+        gy: [SVector<f32, H>; T],
+    ) -> [SVector<f32, X>; T] {
+        self.bp(gy)
+    }
+}
+
+impl<
+        const X: usize,
+        const H: usize,
+        const T: usize,
+        const HX: usize,
+        O,
+        const PEEPHOLE: bool,
+    > Parameters for Lstm<X, H, T, HX, O, PEEPHOLE>
+where
+    O: OptimizerFactory<H, HX> + OptimizerFactory<H, 1>,
+{
+    fn visit_params(
+        &mut self,
+        f: &mut dyn FnMut(&mut [f32], &mut [f32]),
+    ) {
+        f(self.wf.as_mut_slice(), self.dwf.as_mut_slice());
+        f(self.bf.as_mut_slice(), self.dbf.as_mut_slice());
+        f(self.wi.as_mut_slice(), self.dwi.as_mut_slice());
+        f(self.bi.as_mut_slice(), self.dbi.as_mut_slice());
+        f(self.wc.as_mut_slice(), self.dwc.as_mut_slice());
+        f(self.bc.as_mut_slice(), self.dbc.as_mut_slice());
+        f(self.wo.as_mut_slice(), self.dwo.as_mut_slice());
+        f(self.bo.as_mut_slice(), self.dbo.as_mut_slice());
+        if PEEPHOLE {
+            f(
+                self.pf.as_mut_slice(),
+                self.dpf.as_mut_slice(),
+            );
+            f(
+                self.pi.as_mut_slice(),
+                self.dpi.as_mut_slice(),
+            );
+            f(
+                self.po.as_mut_slice(),
+                self.dpo.as_mut_slice(),
+            );
+        }
+    }
+
+    fn update_params_scaled(
+        &mut self,
+        lr_scale: f32,
+        weight_decay: f32,
+    ) {
+        self.optwf.update_weight(
+            &mut self.wf,
+            &self.dwf,
+            lr_scale,
+            weight_decay,
+        );
+        self.optbf.update_param_scaled(
+            &mut self.bf,
+            &self.dbf,
+            lr_scale,
+        );
+        self.optwi.update_weight(
+            &mut self.wi,
+            &self.dwi,
+            lr_scale,
+            weight_decay,
+        );
+        self.optbi.update_param_scaled(
+            &mut self.bi,
+            &self.dbi,
+            lr_scale,
+        );
+        self.optwc.update_weight(
+            &mut self.wc,
+            &self.dwc,
+            lr_scale,
+            weight_decay,
+        );
+        self.optbc.update_param_scaled(
+            &mut self.bc,
+            &self.dbc,
+            lr_scale,
+        );
+        self.optwo.update_weight(
+            &mut self.wo,
+            &self.dwo,
+            lr_scale,
+            weight_decay,
+        );
+        self.optbo.update_param_scaled(
+            &mut self.bo,
+            &self.dbo,
+            lr_scale,
+        );
+        if PEEPHOLE {
+            self.optpf.update_weight(
+                &mut self.pf,
+                &self.dpf,
+                lr_scale,
+                weight_decay,
+            );
+            self.optpi.update_weight(
+                &mut self.pi,
+                &self.dpi,
+                lr_scale,
+                weight_decay,
+            );
+            self.optpo.update_weight(
+                &mut self.po,
+                &self.dpo,
+                lr_scale,
+                weight_decay,
+            );
+        }
+    }
+
+    fn finish_params(&mut self) {
+        self.optwf.finish(&mut self.wf);
+        self.optbf.finish(&mut self.bf);
+        self.optwi.finish(&mut self.wi);
+        self.optbi.finish(&mut self.bi);
+        self.optwc.finish(&mut self.wc);
+        self.optbc.finish(&mut self.bc);
+        self.optwo.finish(&mut self.wo);
+        self.optbo.finish(&mut self.bo);
+        if PEEPHOLE {
+            self.optpf.finish(&mut self.pf);
+            self.optpi.finish(&mut self.pi);
+            self.optpo.finish(&mut self.po);
+        }
+    }
+}
+
+#[cfg(test)]
+fn check_lstm_gradients<const PEEPHOLE: bool>() {
+    use super::assert_recurrent_gradients;
+    use crate::optimizers::sgd::SgdFactory;
+
+    crate::rng::reseed(5);
+    let mut layer = Lstm::<
+        2,
+        3,
+        4,
+        5,
+        SgdFactory<1, 1>,
+        PEEPHOLE,
+    >::with_init::<
+        Uniform<1, 2>,
+        Uniform<1, 2>,
+        Uniform<1, 2>,
+        Constant<1, 1>,
+    >();
+    // nonzero peepholes so that their paths get checked too
+    layer.pf = SVector::repeat(0.3);
+    layer.pi = SVector::repeat(-0.2);
+    layer.po = SVector::repeat(0.1);
+    assert_recurrent_gradients(&mut layer);
+}
+
+#[test]
+fn test_lstm_gradients() {
+    check_lstm_gradients::<false>();
+}
+
+#[test]
+fn test_peephole_lstm_gradients() {
+    check_lstm_gradients::<true>();
+}
+
+#[test]
+fn test_stateful_lstm() {
+    use super::gather_params;
+    use super::scatter_params;
+    use crate::optimizers::sgd::SgdFactory;
+
+    type O = SgdFactory<1, 1>;
+
+    crate::rng::reseed(23);
+    let mut full = Lstm::<2, 3, 4, 5, O>::new();
+    let mut window =
+        Lstm::<2, 3, 2, 5, O>::new().stateful();
+    scatter_params(&mut window, &gather_params(&mut full));
+
+    let x: [SVector<f32, 2>; 4] =
+        std::array::from_fn(|t| {
+            SVector::from_fn(|i, _| {
+                ((t * 2 + i) as f32).sin()
+            })
+        });
+    let y = full.ff(x);
+    // two consecutive windows see the same states as one pass
+    let y0 = window.ff([x[0], x[1]]);
+    let y1 = window.ff([x[2], x[3]]);
+    for t in 0..2 {
+        assert!((y[t] - y0[t]).norm() < 1e-6);
+        assert!((y[t + 2] - y1[t]).norm() < 1e-6);
+    }
+
+    window.reset_state();
+    let y0 = window.ff([x[0], x[1]]);
+    assert!((y[1] - y0[1]).norm() < 1e-6);
+}
+
+#[test]
+fn test_lstm_state_gradients() {
+    use crate::optimizers::sgd::SgdFactory;
+
+    type L = Lstm<2, 3, 4, 5, SgdFactory<1, 1>>;
+
+    // a decoder starting from the final state of an encoder, the
+    // loss only reaches the encoder through that state
+    crate::rng::reseed(8);
+    let mut enc = L::new();
+    let mut dec = L::new();
+    let x: [SVector<f32, 2>; 4] =
+        std::array::from_fn(|t| {
+            SVector::from_fn(|i, _| {
+                ((t * 2 + i) as f32 * 0.7).sin()
+            })
+        });
+    let c: [SVector<f32, 3>; 4] =
+        std::array::from_fn(|t| {
+            SVector::from_fn(|i, _| {
+                ((t * 3 + i) as f32 * 1.3).cos()
+            })
+        });
+    // the decoder input does not depend on x
+    let d = [SVector::<f32, 2>::new(0.5, -0.5); 4];
+    let loss = |enc: &mut L,
+                dec: &mut L,
+                x: [SVector<f32, 2>; 4]| {
+        enc.ff_masked(x, 3);
+        let (h, c0) = enc.final_state();
+        dec.set_initial_state(h, c0);
+        let y = dec.ff(d);
+        (0..4).map(|t| c[t].dot(&y[t])).sum::<f32>()
+    };
+
+    loss(&mut enc, &mut dec, x);
+    dec.bp(c);
+    let (gh, gc) = dec.initial_state_grad();
+    let gx =
+        enc.bp_with_state([SVector::zeros(); 4], gh, gc);
+    for t in 0..4 {
+        for i in 0..2 {
+            let (mut xp, mut xm) = (x, x);
+            xp[t][i] += 1e-2;
+            xm[t][i] -= 1e-2;
+            let fd = (loss(&mut enc, &mut dec, xp)
+                - loss(&mut enc, &mut dec, xm))
+                / 2e-2;
+            assert!(
+                (fd - gx[t][i]).abs()
+                    < 2e-3 * (1. + fd.abs()),
+                "input {t},{i}: backprop {} finite diff \
+                 {fd}",
+                gx[t][i]
+            );
+        }
+    }
+    // the padding is not read
+    assert_eq!(gx[3].norm(), 0.);
+}
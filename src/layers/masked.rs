@@ -0,0 +1,34 @@
+use nalgebra::SMatrix;
+
+// sequence of N rows of dimension M, only the first len hold
+// tokens and the rest is zero padding
+#[derive(Clone, Copy, Debug)]
+pub struct Masked<const N: usize, const M: usize> {
+    pub x: SMatrix<f32, N, M>,
+    pub len: usize,
+}
+
+impl<const N: usize, const M: usize> Masked<N, M> {
+    pub fn new(x: SMatrix<f32, N, M>, len: usize) -> Self {
+        assert!(
+            0 < len && len <= N,
+            "Sequence length {len} out of 1..={N}"
+        );
+        Self { x, len }
+    }
+
+    // sequence without padding
+    pub fn full(x: SMatrix<f32, N, M>) -> Self {
+        Self::new(x, N)
+    }
+}
+
+// zeroes the rows from len on, works both on values and on their
+// gradients
+pub fn mask_rows<const N: usize, const C: usize>(
+    mut x: SMatrix<f32, N, C>,
+    len: usize,
+) -> SMatrix<f32, N, C> {
+    x.rows_mut(len, N - len).fill(0.);
+    x
+}
@@ -0,0 +1,62 @@
+use nalgebra::SVector;
+
+// average over the first len elements of a sequence, the padding
+// after them is ignored
+pub struct MeanPool<const X: usize, const T: usize> {
+    len: usize,
+}
+
+impl<const X: usize, const T: usize> MeanPool<X, T> {
+    pub fn new() -> Self {
+        let len = T;
+        Self { len }
+    }
+
+    // feedforward
+    pub fn ff(
+        &mut self,
+        x: [SVector<f32, X>; T],
+        len: usize,
+    ) -> SVector<f32, X> {
+        self.len = len;
+        x[..len].iter().sum::<SVector<f32, X>>()
+            / len as f32
+    }
+
+    // backprop
+    pub fn bp(
+        &mut self,
+        g: SVector<f32, X>,
+    ) -> [SVector<f32, X>; T] {
+        let mut gx = [SVector::zeros(); T];
+        let g = g / self.len as f32;
+        gx[..self.len].fill(g);
+        gx
+    }
+}
+
+impl<const X: usize, const T: usize> Default
+    for MeanPool<X, T>
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[test]
+fn test_meanpool_ignores_padding() {
+    let mut pool = MeanPool::<2, 4>::new();
+    let x = [
+        SVector::<f32, 2>::new(1., 2.),
+        SVector::<f32, 2>::new(3., 4.),
+        SVector::<f32, 2>::new(100., 100.),
+        SVector::<f32, 2>::new(-100., 7.),
+    ];
+    assert_eq!(
+        pool.ff(x, 2),
+        SVector::<f32, 2>::new(2., 3.)
+    );
+    let gx = pool.bp(SVector::<f32, 2>::new(1., 1.));
+    assert_eq!(gx[1], SVector::<f32, 2>::new(0.5, 0.5));
+    assert_eq!(gx[2].norm(), 0.);
+}
@@ -1,8 +1,14 @@
+use nalgebra::SVector;
+
 pub mod actlayer;
 pub mod attention;
+pub mod bidirectional;
 pub mod conv;
+pub mod crossattention;
 pub mod embedding;
+pub mod gru;
 pub mod maxpool;
+pub mod paramactlayer;
 pub mod relu2d;
 pub mod rnncell;
 pub mod seq2d;
@@ -11,7 +17,234 @@ pub mod softmax;
 pub mod softmax2d;
 //pub mod tokenizer;
 pub mod dense;
+pub mod dropout;
 pub mod layernorm;
+pub mod lookup;
 pub mod lstm;
+pub mod masked;
+// no model pools its sequences yet
+#[allow(dead_code)]
+pub mod meanpool;
 pub mod posencoder;
 pub mod randembedding;
+pub mod stacked;
+pub mod timedense;
+
+// learnable parameters of a layer together with the gradients
+// computed by its last bp, which only get applied to the
+// parameters when update_params is called
+pub trait Parameters {
+    // calls f with every parameter matrix and its gradient,
+    // always in the same order
+    fn visit_params(
+        &mut self,
+        f: &mut dyn FnMut(&mut [f32], &mut [f32]),
+    );
+    // runs the optimizers on the stored gradients
+    fn update_params(&mut self) {
+        self.update_params_scaled(1., 0.);
+    }
+    // same with the learning rates multiplied by lr_scale and an
+    // l2 penalty of weight_decay on the weights (not the biases)
+    fn update_params_scaled(
+        &mut self,
+        lr_scale: f32,
+        weight_decay: f32,
+    );
+    // lets the optimizers write their final weights once
+    // training is over
+    fn finish_params(&mut self);
+    // switches layers like dropout between training and
+    // inference
+    fn set_training(&mut self, _training: bool) {}
+}
+
+// layer made of other named layers (models, dense stacks...)
+pub trait Layers {
+    fn visit_layers(
+        &mut self,
+        f: &mut dyn FnMut(&str, &mut dyn Parameters),
+    );
+}
+
+// layer going over a sequence of T inputs with one output per
+// timestep (RnnCell, Lstm, Gru...)
+pub trait Recurrent<
+    const X: usize,
+    const Y: usize,
+    const T: usize,
+>: Parameters
+{
+    fn ff(
+        &mut self,
+        x: [SVector<f32, X>; T],
+    ) -> [SVector<f32, Y>; T] {
+        self.ff_masked(x, T)
+    }
+    // only reads the first len inputs, the rest is padding: the
+    // outputs from len on are zero and bp ignores their gradients
+    fn ff_masked(
+        &mut self,
+        x: [SVector<f32, X>; T],
+        len: usize,
+    ) -> [SVector<f32, Y>; T];
+    // forgets the state carried over by stateful layers
+    fn reset_state(&mut self) {}
+    // whether the state carries over from one ff to the next
+    fn is_stateful(&self) -> bool {
+        false
+    }
+    fn bp(
+        &mut self,
+        gy: [SVector<f32, Y>; T],
+    ) -> [SVector<f32, X>; T];
+}
+
+impl<T: Layers> Parameters for T {
+    fn visit_params(
+        &mut self,
+        f: &mut dyn FnMut(&mut [f32], &mut [f32]),
+    ) {
+        self.visit_layers(&mut |_, layer| {
+            layer.visit_params(f)
+        });
+    }
+
+    fn update_params_scaled(
+        &mut self,
+        lr_scale: f32,
+        weight_decay: f32,
+    ) {
+        self.visit_layers(&mut |_, layer| {
+            layer.update_params_scaled(
+                lr_scale,
+                weight_decay,
+            )
+        });
+    }
+
+    fn finish_params(&mut self) {
+        self.visit_layers(&mut |_, layer| {
+            layer.finish_params()
+        });
+    }
+
+    fn set_training(&mut self, training: bool) {
+        self.visit_layers(&mut |_, layer| {
+            layer.set_training(training)
+        });
+    }
+}
+
+// all the parameters flattened in visit order
+pub fn gather_params<P: Parameters + ?Sized>(
+    params: &mut P,
+) -> Vec<f32> {
+    let mut out = Vec::new();
+    params
+        .visit_params(&mut |w, _| out.extend_from_slice(w));
+    out
+}
+
+// inverse of gather_params
+pub fn scatter_params<P: Parameters + ?Sized>(
+    params: &mut P,
+    values: &[f32],
+) {
+    let mut values = values.iter();
+    params.visit_params(&mut |w, _| {
+        w.iter_mut()
+            .zip(&mut values)
+            .for_each(|(wi, vi)| *wi = *vi);
+    });
+}
+
+// compares the gradients stored by backward with central finite
+// differences of loss, which has to run the feedforward
+#[cfg(test)]
+pub fn assert_param_gradients<P: Parameters>(
+    layer: &mut P,
+    mut loss: impl FnMut(&mut P) -> f32,
+    backward: impl FnOnce(&mut P),
+) {
+    const EPS: f32 = 1e-2;
+
+    loss(layer);
+    backward(layer);
+    let mut grads = Vec::new();
+    layer.visit_params(&mut |_, g| {
+        grads.extend_from_slice(g)
+    });
+    let params = gather_params(layer);
+    for k in 0..params.len() {
+        let mut p = params.clone();
+        p[k] = params[k] + EPS;
+        scatter_params(layer, &p);
+        let loss_plus = loss(layer);
+        p[k] = params[k] - EPS;
+        scatter_params(layer, &p);
+        let loss_minus = loss(layer);
+        let fd = (loss_plus - loss_minus) / (2. * EPS);
+        assert!(
+            (fd - grads[k]).abs() < 2e-3 * (1. + fd.abs()),
+            "param {k}: backprop {} finite diff {fd}",
+            grads[k]
+        );
+    }
+    scatter_params(layer, &params);
+}
+
+// gradient check of a recurrent layer on a fixed sequence, for
+// the loss sum of c_t . y_t
+#[cfg(test)]
+pub fn assert_recurrent_gradients<
+    const X: usize,
+    const Y: usize,
+    const T: usize,
+    R: Recurrent<X, Y, T>,
+>(
+    layer: &mut R,
+) {
+    let x: [SVector<f32, X>; T] =
+        std::array::from_fn(|t| {
+            SVector::from_fn(|i, _| {
+                ((t * X + i) as f32 * 0.7).sin()
+            })
+        });
+    let c: [SVector<f32, Y>; T] =
+        std::array::from_fn(|t| {
+            SVector::from_fn(|i, _| {
+                ((t * Y + i) as f32 * 1.3).cos()
+            })
+        });
+    let loss = |layer: &mut R, x: [SVector<f32, X>; T]| {
+        let y = layer.ff(x);
+        (0..T).map(|t| c[t].dot(&y[t])).sum::<f32>()
+    };
+    assert_param_gradients(
+        layer,
+        |layer| loss(layer, x),
+        |layer| {
+            layer.bp(c);
+        },
+    );
+
+    loss(layer, x);
+    let gx = layer.bp(c);
+    for t in 0..T {
+        for i in 0..X {
+            let (mut xp, mut xm) = (x, x);
+            xp[t][i] += 1e-2;
+            xm[t][i] -= 1e-2;
+            let fd =
+                (loss(layer, xp) - loss(layer, xm)) / 2e-2;
+            assert!(
+                (fd - gx[t][i]).abs()
+                    < 2e-3 * (1. + fd.abs()),
+                "input {t},{i}: backprop {} finite diff \
+                 {fd}",
+                gx[t][i]
+            );
+        }
+    }
+}
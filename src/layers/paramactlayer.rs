@@ -0,0 +1,166 @@
+use std::marker::PhantomData;
+
+use nalgebra::SVector;
+
+use super::Parameters;
+use crate::activation::ParametricActivation;
+use crate::optimizers::Optimizer;
+use crate::optimizers::OptimizerFactory;
+
+// activation layer whose per unit parameters (PReLU slope,
+// Swish beta, ...) are trained like any other weight
+pub struct ParamActivationLayer<
+    const N: usize,
+    F,
+    O: OptimizerFactory<N, 1>,
+> {
+    z: SVector<f32, N>,
+    p: SVector<f32, N>,
+    dp: SVector<f32, N>,
+    f: PhantomData<F>,
+    optp: <O as OptimizerFactory<N, 1>>::Optimizer,
+}
+
+impl<const N: usize, F, O> ParamActivationLayer<N, F, O>
+where
+    F: ParametricActivation,
+    O: OptimizerFactory<N, 1>,
+{
+    pub fn new() -> Self {
+        let z = SVector::zeros();
+        let p = SVector::repeat(F::init());
+        let dp = SVector::zeros();
+        let f = PhantomData;
+        let optp =
+            <O as OptimizerFactory<N, 1>>::Optimizer::init_bias(
+            );
+
+        Self { z, p, dp, f, optp }
+    }
+
+    // feedforward
+    pub fn ff(
+        &mut self,
+        x: SVector<f32, N>,
+    ) -> SVector<f32, N> {
+        self.z = x;
+        self.z.zip_map(&self.p, F::func)
+    }
+
+    // backprop
+    pub fn bp(
+        &mut self,
+        g: SVector<f32, N>,
+    ) -> SVector<f32, N> {
+        self.dp = self
+            .z
+            .zip_map(&self.p, F::deriv_param)
+            .component_mul(&g);
+        self.z.zip_map(&self.p, F::deriv).component_mul(&g)
+    }
+}
+
+impl<const N: usize, F, O> Default
+    for ParamActivationLayer<N, F, O>
+where
+    F: ParametricActivation,
+    O: OptimizerFactory<N, 1>,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize, F, O> Parameters
+    for ParamActivationLayer<N, F, O>
+where
+    O: OptimizerFactory<N, 1>,
+{
+    fn visit_params(
+        &mut self,
+        f: &mut dyn FnMut(&mut [f32], &mut [f32]),
+    ) {
+        f(self.p.as_mut_slice(), self.dp.as_mut_slice());
+    }
+
+    fn update_params_scaled(
+        &mut self,
+        lr_scale: f32,
+        _weight_decay: f32,
+    ) {
+        // the activation parameters are not decayed
+        self.optp.update_param_scaled(
+            &mut self.p,
+            &self.dp,
+            lr_scale,
+        );
+    }
+
+    fn finish_params(&mut self) {
+        self.optp.finish(&mut self.p);
+    }
+}
+
+#[cfg(test)]
+fn check_param_activation_gradients<
+    F: ParametricActivation,
+>() {
+    use super::assert_param_gradients;
+    use crate::optimizers::sgd::SgdFactory;
+
+    // no input at 0, where PReLU has a kink
+    let x = SVector::<f32, 4>::new(-1.3, -0.4, 0.6, 1.7);
+    let c = SVector::<f32, 4>::new(0.5, -1.2, 0.8, 0.3);
+    let loss = |layer: &mut ParamActivationLayer<
+        4,
+        F,
+        SgdFactory<1, 10>,
+    >,
+                x: SVector<f32, 4>| {
+        c.dot(&layer.ff(x))
+    };
+
+    let mut layer = ParamActivationLayer::new();
+    assert_param_gradients(
+        &mut layer,
+        |layer| loss(layer, x),
+        |layer| {
+            layer.bp(c);
+        },
+    );
+
+    loss(&mut layer, x);
+    let gx = layer.bp(c);
+    for i in 0..4 {
+        let (mut xp, mut xm) = (x, x);
+        xp[i] += 1e-2;
+        xm[i] -= 1e-2;
+        let fd = (loss(&mut layer, xp)
+            - loss(&mut layer, xm))
+            / 2e-2;
+        assert!(
+            (fd - gx[i]).abs() < 2e-3 * (1. + fd.abs())
+        );
+    }
+
+    // the parameters are learned with the layer optimizer
+    let p = layer.p;
+    let dp = layer.dp;
+    layer.update_params();
+    assert!((layer.p - (p - 0.1 * dp)).norm() < 1e-6);
+    assert!(dp.norm() > 0.);
+}
+
+#[test]
+fn test_prelu_gradients() {
+    use crate::activation::prelu::PRelu;
+
+    check_param_activation_gradients::<PRelu>();
+}
+
+#[test]
+fn test_swish_gradients() {
+    use crate::activation::swish::Swish;
+
+    check_param_activation_gradients::<Swish>();
+}
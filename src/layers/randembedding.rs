@@ -4,6 +4,8 @@ use nalgebra::SMatrix;
 use nalgebra::SVector;
 use rand::Rng;
 
+use crate::rng::with_rng;
+
 #[derive(Default)]
 // N: number of tokens (words in sentence), M: dimension of the embedding
 pub struct RandEmbedding<const N: usize, const M: usize> {
@@ -24,22 +26,25 @@ impl<const N: usize, const M: usize> RandEmbedding<N, M> {
             if i >= N {
                 break;
             }
-            let v = self
-                .map
-                .entry(word.to_string())
-                .or_insert({
-                    let mut v = SVector::zeros();
-                    let mut rng = rand::thread_rng();
-                    let uniform =
-                        rand_distr::Uniform::new(-1., 1.);
+            out.set_row(i, &self.get(word).transpose());
+        }
+        out
+    }
+
+    fn get(&mut self, word: &str) -> SVector<f32, M> {
+        *self.map.entry(word.to_string()).or_insert_with(
+            || {
+                let mut v = SVector::zeros();
+                let uniform =
+                    rand_distr::Uniform::new(-1., 1.);
+                with_rng(|rng| {
                     for i in 0..M {
                         v[i] = rng.sample(uniform);
                     }
-                    v
                 });
-            out.set_row(i, &v.transpose());
-        }
-        out
+                v
+            },
+        )
     }
 }
 
@@ -1,13 +1,24 @@
+use std::marker::PhantomData;
+
 use nalgebra::SMatrix;
 use nalgebra::SVector;
-use rand::Rng;
 
+use super::Parameters;
+use super::Recurrent;
 use crate::activation::deriv_all;
 use crate::activation::func_all;
 use crate::activation::tanh::Tanh;
+use crate::activation::ActivationFunction;
+use crate::initializers::constant::Zeros;
+use crate::initializers::uniform::Uniform;
+use crate::initializers::Initializer;
 use crate::optimizers::Optimizer;
 use crate::optimizers::OptimizerFactory;
 
+// z = Wx x + Wh h + bh
+// h' = F(z)
+// y = Wy h' + by
+// without PROJECT the output is h' itself and Y has to be H
 pub struct RnnCell<
     const X: usize,
     const Y: usize,
@@ -15,18 +26,39 @@ pub struct RnnCell<
     const T: usize,
     O: OptimizerFactory<H, X>
         + OptimizerFactory<H, H>
-        + OptimizerFactory<Y, H>,
+        + OptimizerFactory<H, 1>
+        + OptimizerFactory<Y, H>
+        + OptimizerFactory<Y, 1>,
+    F = Tanh,
+    const PROJECT: bool = true,
 > {
+    // timesteps read by the last ff
+    len: usize,
     x: [SVector<f32, X>; T],
     y: [SVector<f32, Y>; T],
     h: [SVector<f32, H>; T],
+    // hidden state the window starts from and ends with, in
+    // stateful mode the next window starts where this one ended
+    stateful: bool,
+    h0: SVector<f32, H>,
+    hn: SVector<f32, H>,
     z: [SVector<f32, H>; T],
     wx: SMatrix<f32, H, X>,
     wh: SMatrix<f32, H, H>,
+    bh: SVector<f32, H>,
     wy: SMatrix<f32, Y, H>,
+    by: SVector<f32, Y>,
+    dwx: SMatrix<f32, H, X>,
+    dwh: SMatrix<f32, H, H>,
+    dbh: SVector<f32, H>,
+    dwy: SMatrix<f32, Y, H>,
+    dby: SVector<f32, Y>,
+    act: PhantomData<F>,
     optwx: <O as OptimizerFactory<H, X>>::Optimizer,
     optwh: <O as OptimizerFactory<H, H>>::Optimizer,
+    optbh: <O as OptimizerFactory<H, 1>>::Optimizer,
     optwy: <O as OptimizerFactory<Y, H>>::Optimizer,
+    optby: <O as OptimizerFactory<Y, 1>>::Optimizer,
 }
 
 impl<
@@ -35,78 +67,145 @@ impl<
         const H: usize,
         const T: usize,
         O,
-    > RnnCell<X, Y, H, T, O>
+        F,
+        const PROJECT: bool,
+    > RnnCell<X, Y, H, T, O, F, PROJECT>
 where
     O: OptimizerFactory<H, X>
         + OptimizerFactory<H, H>
-        + OptimizerFactory<Y, H>,
+        + OptimizerFactory<H, 1>
+        + OptimizerFactory<Y, H>
+        + OptimizerFactory<Y, 1>,
+    F: ActivationFunction,
 {
     pub fn new() -> Self {
+        Self::with_init::<Uniform<1, 1>, Uniform<1, 1>, Zeros>(
+        )
+    }
+
+    // Wx and Wy initialized by IW, the recurrent Wh by IH and
+    // the biases by IB
+    pub fn with_init<
+        IW: Initializer,
+        IH: Initializer,
+        IB: Initializer,
+    >() -> Self {
+        assert!(
+            PROJECT || Y == H,
+            "RnnCell without projection needs Y = H"
+        );
+        let len = T;
         let x = [SVector::zeros(); T];
         let y = [SVector::zeros(); T];
         let h = [SVector::zeros(); T];
+        let stateful = false;
+        let h0 = SVector::zeros();
+        let hn = SVector::zeros();
         let z = [SVector::zeros(); T];
 
-        let mut wx = SMatrix::zeros();
-        let mut wy = SMatrix::zeros();
-        let mut wh = SMatrix::zeros();
-
-        // randomize weights
-        let mut rng = rand::thread_rng();
-        let uniform = rand_distr::Uniform::new(-1.0, 1.0);
-        for i in 0..H {
-            for j in 0..X {
-                wx[(i, j)] = rng.sample(uniform);
-            }
-        }
-        for i in 0..Y {
-            for j in 0..H {
-                wy[(i, j)] = rng.sample(uniform);
-            }
-        }
-        for i in 0..H {
-            for j in 0..H {
-                wh[(i, j)] = rng.sample(uniform);
-            }
-        }
+        let wx = IW::init(X, H);
+        let wy = IW::init(H, Y);
+        let wh = IH::init(H, H);
+        let bh = IB::init(X, H);
+        let by = IB::init(H, Y);
+        let dwx = SMatrix::zeros();
+        let dwh = SMatrix::zeros();
+        let dbh = SVector::zeros();
+        let dwy = SMatrix::zeros();
+        let dby = SVector::zeros();
 
+        let act = PhantomData;
         let optwx =
             <O as OptimizerFactory<H, X>>::Optimizer::init(
             );
         let optwh =
             <O as OptimizerFactory<H, H>>::Optimizer::init(
             );
+        let optbh =
+            <O as OptimizerFactory<H, 1>>::Optimizer::init_bias(
+            );
         let optwy =
             <O as OptimizerFactory<Y, H>>::Optimizer::init(
             );
+        let optby =
+            <O as OptimizerFactory<Y, 1>>::Optimizer::init_bias(
+            );
 
         Self {
+            len,
             x,
             y,
             h,
+            stateful,
+            h0,
+            hn,
             wx,
             wh,
+            bh,
             wy,
+            by,
             z,
+            dwx,
+            dwh,
+            dbh,
+            dwy,
+            dby,
+            act,
             optwx,
             optwh,
+            optbh,
             optwy,
+            optby,
         }
     }
 
+    // carries the state over consecutive calls of ff, bp still
+    // stops at the start of each window (truncated bptt)
+    pub fn stateful(mut self) -> Self {
+        self.stateful = true;
+        self
+    }
+
+    // starts the next window from a zero state
+    pub fn reset_state(&mut self) {
+        self.hn = SVector::zeros();
+    }
+
     // feedforward
     pub fn ff(
         &mut self,
         x: [SVector<f32, X>; T],
+    ) -> [SVector<f32, Y>; T] {
+        self.ff_masked(x, T)
+    }
+
+    // feedforward over the first len timesteps
+    pub fn ff_masked(
+        &mut self,
+        x: [SVector<f32, X>; T],
+        len: usize,
     ) -> [SVector<f32, Y>; T] {
         self.x = x;
-        for t in 0..T {
-            self.z[t] = self.wx * self.x[t];
-            if t != 0 {
-                self.z[t] += self.wh * self.h[t - 1]
-            }
-            self.h[t] = func_all::<H, 1, Tanh>(&self.z[t]);
-            self.y[t] = self.wy * self.h[t];
+        self.len = len;
+        if self.stateful {
+            self.h0 = self.hn;
+        }
+        for t in 0..len {
+            self.z[t] = self.wx * self.x[t]
+                + self.wh * self.h_prev(t)
+                + self.bh;
+            self.h[t] = func_all::<H, 1, F>(&self.z[t]);
+            self.y[t] = if PROJECT {
+                self.wy * self.h[t] + self.by
+            } else {
+                SVector::from_column_slice(
+                    self.h[t].as_slice(),
+                )
+            };
+        }
+        self.hn = self.h[len - 1];
+        for t in len..T {
+            self.y[t] = SVector::zeros();
         }
         self.y.clone()
     }
@@ -118,26 +217,221 @@ where
     ) -> [SVector<f32, X>; T] {
         let mut gh = SVector::zeros();
         let mut gx = [SVector::zeros(); T];
-        let mut dwx = SMatrix::zeros();
-        let mut dwy = SMatrix::zeros();
-        let mut dwh = SMatrix::zeros();
-        for t in (0..T).rev() {
-            dwy += gy[t] * self.h[t].transpose();
-            let g = self.wy.transpose() * gy[t] + &gh;
-            let g = deriv_all::<H, 1, Tanh>(&self.z[t])
-                .component_mul(&g);
-            dwx += g * self.x[t].transpose();
-            if t != 0 {
-                dwh += g * self.h[t - 1].transpose();
-            }
+        self.dwx = SMatrix::zeros();
+        self.dwh = SMatrix::zeros();
+        self.dbh = SVector::zeros();
+        self.dwy = SMatrix::zeros();
+        self.dby = SVector::zeros();
+        for t in (0..self.len).rev() {
+            let g = if PROJECT {
+                self.dwy += gy[t] * self.h[t].transpose();
+                self.dby += gy[t];
+                self.wy.transpose() * gy[t]
+            } else {
+                SVector::from_column_slice(gy[t].as_slice())
+            };
+            let g = deriv_all::<H, 1, F>(&self.z[t])
+                .component_mul(&(g + gh));
+            self.dwx += g * self.x[t].transpose();
+            self.dwh += g * self.h_prev(t).transpose();
+            self.dbh += g;
             gx[t] = self.wx.transpose() * g;
             gh = self.wh.transpose() * g;
         }
 
-        self.optwx.update_param(&mut self.wx, &dwx);
-        self.optwh.update_param(&mut self.wh, &dwh);
-        self.optwy.update_param(&mut self.wy, &dwy);
-
         gx
     }
+
+    fn h_prev(&self, t: usize) -> SVector<f32, H> {
+        if t != 0 {
+            self.h[t - 1]
+        } else {
+            self.h0
+        }
+    }
+}
+
+impl<
+        const X: usize,
+        const Y: usize,
+        const H: usize,
+        const T: usize,
+        O,
+        F,
+        const PROJECT: bool,
+    > Recurrent<X, Y, T>
+    for RnnCell<X, Y, H, T, O, F, PROJECT>
+where
+    O: OptimizerFactory<H, X>
+        + OptimizerFactory<H, H>
+        + OptimizerFactory<H, 1>
+        + OptimizerFactory<Y, H>
+        + OptimizerFactory<Y, 1>,
+    F: ActivationFunction,
+{
+    fn ff_masked(
+        &mut self,
+        x: [SVector<f32, X>; T],
+        len: usize,
+    ) -> [SVector<f32, Y>; T] {
+        self.ff_masked(x, len)
+    }
+
+    fn reset_state(&mut self) {
+        self.reset_state()
+    }
+
+    fn is_stateful(&self) -> bool {
+        self.stateful
+    }
+
+    fn bp(
+        &mut self,
+        gy: [SVector<f32, Y>; T],
+    ) -> [SVector<f32, X>; T] {
+        self.bp(gy)
+    }
+}
+
+impl<
+        const X: usize,
+        const Y: usize,
+        const H: usize,
+        const T: usize,
+        O,
+        F,
+        const PROJECT: bool,
+    > Parameters for RnnCell<X, Y, H, T, O, F, PROJECT>
+where
+    O: OptimizerFactory<H, X>
+        + OptimizerFactory<H, H>
+        + OptimizerFactory<H, 1>
+        + OptimizerFactory<Y, H>
+        + OptimizerFactory<Y, 1>,
+    F: ActivationFunction,
+{
+    fn visit_params(
+        &mut self,
+        f: &mut dyn FnMut(&mut [f32], &mut [f32]),
+    ) {
+        f(self.wx.as_mut_slice(), self.dwx.as_mut_slice());
+        f(self.wh.as_mut_slice(), self.dwh.as_mut_slice());
+        f(self.bh.as_mut_slice(), self.dbh.as_mut_slice());
+        if PROJECT {
+            f(
+                self.wy.as_mut_slice(),
+                self.dwy.as_mut_slice(),
+            );
+            f(
+                self.by.as_mut_slice(),
+                self.dby.as_mut_slice(),
+            );
+        }
+    }
+
+    fn update_params_scaled(
+        &mut self,
+        lr_scale: f32,
+        weight_decay: f32,
+    ) {
+        self.optwx.update_weight(
+            &mut self.wx,
+            &self.dwx,
+            lr_scale,
+            weight_decay,
+        );
+        self.optwh.update_weight(
+            &mut self.wh,
+            &self.dwh,
+            lr_scale,
+            weight_decay,
+        );
+        self.optbh.update_param_scaled(
+            &mut self.bh,
+            &self.dbh,
+            lr_scale,
+        );
+        if PROJECT {
+            self.optwy.update_weight(
+                &mut self.wy,
+                &self.dwy,
+                lr_scale,
+                weight_decay,
+            );
+            self.optby.update_param_scaled(
+                &mut self.by,
+                &self.dby,
+                lr_scale,
+            );
+        }
+    }
+
+    fn finish_params(&mut self) {
+        self.optwx.finish(&mut self.wx);
+        self.optwh.finish(&mut self.wh);
+        self.optbh.finish(&mut self.bh);
+        if PROJECT {
+            self.optwy.finish(&mut self.wy);
+            self.optby.finish(&mut self.by);
+        }
+    }
+}
+
+#[test]
+fn test_rnncell_gradients() {
+    use super::assert_recurrent_gradients;
+    use crate::activation::sigmoid::Sigmoid;
+    use crate::optimizers::sgd::SgdFactory;
+
+    type O = SgdFactory<1, 1>;
+
+    crate::rng::reseed(29);
+    let mut projected = RnnCell::<2, 4, 3, 4, O>::with_init::<
+        Uniform<1, 1>,
+        Uniform<1, 1>,
+        Uniform<1, 2>,
+    >();
+    assert_recurrent_gradients(&mut projected);
+
+    let mut hidden =
+        RnnCell::<2, 3, 3, 4, O, Sigmoid, false>::with_init::<
+            Uniform<1, 1>,
+            Uniform<1, 1>,
+            Uniform<1, 2>,
+        >();
+    assert_recurrent_gradients(&mut hidden);
+}
+
+#[test]
+fn test_stateful_rnncell() {
+    use super::gather_params;
+    use super::scatter_params;
+    use crate::optimizers::sgd::SgdFactory;
+
+    type O = SgdFactory<1, 1>;
+
+    crate::rng::reseed(31);
+    let mut full = RnnCell::<2, 4, 3, 4, O>::new();
+    let mut window =
+        RnnCell::<2, 4, 3, 2, O>::new().stateful();
+    scatter_params(&mut window, &gather_params(&mut full));
+
+    let x: [SVector<f32, 2>; 4] =
+        std::array::from_fn(|t| {
+            SVector::from_fn(|i, _| {
+                ((t * 2 + i) as f32).sin()
+            })
+        });
+    let y = full.ff(x);
+    // two consecutive windows see the same states as one pass
+    let y0 = window.ff([x[0], x[1]]);
+    let y1 = window.ff([x[2], x[3]]);
+    for t in 0..2 {
+        assert!((y[t] - y0[t]).norm() < 1e-6);
+        assert!((y[t + 2] - y1[t]).norm() < 1e-6);
+    }
+
+    window.reset_state();
+    let y0 = window.ff([x[0], x[1]]);
+    assert!((y[1] - y0[1]).norm() < 1e-6);
 }
@@ -1,11 +1,13 @@
 use std::marker::PhantomData;
 
 use nalgebra::SMatrix;
-use rand::Rng;
 
+use super::Parameters;
 use crate::activation::deriv_all;
 use crate::activation::func_all;
 use crate::activation::ActivationFunction;
+use crate::initializers::uniform::Uniform;
+use crate::initializers::Initializer;
 use crate::optimizers::Optimizer;
 use crate::optimizers::OptimizerFactory;
 
@@ -20,6 +22,8 @@ pub struct Dense2D<
     w: SMatrix<f32, Y, X>,
     b: SMatrix<f32, Y, N>,
     z: SMatrix<f32, Y, N>,
+    dw: SMatrix<f32, Y, X>,
+    db: SMatrix<f32, Y, N>,
     act: PhantomData<F>,
     optw: <O as OptimizerFactory<Y, X>>::Optimizer,
     optb: <O as OptimizerFactory<Y, N>>::Optimizer,
@@ -37,27 +41,25 @@ where
     O: OptimizerFactory<Y, X> + OptimizerFactory<Y, N>,
 {
     pub fn new() -> Self {
+        Self::with_init::<Uniform<1, 2>, Uniform<1, 2>>()
+    }
+
+    // W initialized by IW and b by IB
+    pub fn with_init<IW: Initializer, IB: Initializer>(
+    ) -> Self {
         let x = SMatrix::zeros();
-        let mut w = SMatrix::zeros();
-        let mut b = SMatrix::zeros();
+        let w = IW::init(X, Y);
+        let b = IB::init(X, Y);
         let z = SMatrix::zeros();
-
-        // randomize W and b
-        let mut rng = rand::thread_rng();
-        let uniform = rand_distr::Uniform::new(-0.5, 0.5);
-        for i in 0..Y {
-            for j in 0..X {
-                w[(i, j)] = rng.sample(uniform);
-            }
-            b[i] = rng.sample(uniform);
-        }
+        let dw = SMatrix::zeros();
+        let db = SMatrix::zeros();
 
         let act = PhantomData;
         let optw =
             <O as OptimizerFactory<Y, X>>::Optimizer::init(
             );
         let optb =
-            <O as OptimizerFactory<Y, N>>::Optimizer::init(
+            <O as OptimizerFactory<Y, N>>::Optimizer::init_bias(
             );
 
         Self {
@@ -65,6 +67,8 @@ where
             w,
             b,
             z,
+            dw,
+            db,
             act,
             optw,
             optb,
@@ -87,11 +91,50 @@ where
         mut g: SMatrix<f32, Y, N>,
     ) -> SMatrix<f32, X, N> {
         g = deriv_all::<Y, N, F>(&self.z).component_mul(&g);
-        let dw = &g * self.x.transpose();
-        let db = &g;
-        let dx = self.w.transpose() * g;
-        self.optw.update_param(&mut self.w, &dw);
-        self.optb.update_param(&mut self.b, db);
-        dx
+        self.dw = &g * self.x.transpose();
+        self.db = g;
+        self.w.transpose() * g
+    }
+}
+
+impl<
+        const X: usize,
+        const Y: usize,
+        const N: usize,
+        F,
+        O,
+    > Parameters for Dense2D<X, Y, N, F, O>
+where
+    O: OptimizerFactory<Y, X> + OptimizerFactory<Y, N>,
+{
+    fn visit_params(
+        &mut self,
+        f: &mut dyn FnMut(&mut [f32], &mut [f32]),
+    ) {
+        f(self.w.as_mut_slice(), self.dw.as_mut_slice());
+        f(self.b.as_mut_slice(), self.db.as_mut_slice());
+    }
+
+    fn update_params_scaled(
+        &mut self,
+        lr_scale: f32,
+        weight_decay: f32,
+    ) {
+        self.optw.update_weight(
+            &mut self.w,
+            &self.dw,
+            lr_scale,
+            weight_decay,
+        );
+        self.optb.update_param_scaled(
+            &mut self.b,
+            &self.db,
+            lr_scale,
+        );
+    }
+
+    fn finish_params(&mut self) {
+        self.optw.finish(&mut self.w);
+        self.optb.finish(&mut self.b);
     }
 }
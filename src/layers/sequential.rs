@@ -2,11 +2,13 @@ use std::marker::PhantomData;
 
 use nalgebra::SMatrix;
 use nalgebra::SVector;
-use rand::Rng;
 
+use super::Parameters;
 use crate::activation::deriv_all;
 use crate::activation::func_all;
 use crate::activation::ActivationFunction;
+use crate::initializers::uniform::Uniform;
+use crate::initializers::Initializer;
 use crate::optimizers::Optimizer;
 use crate::optimizers::OptimizerFactory;
 
@@ -20,6 +22,8 @@ pub struct Sequential<
     w: SMatrix<f32, L2, L1>,
     b: SVector<f32, L2>,
     z: SVector<f32, L2>,
+    dw: SMatrix<f32, L2, L1>,
+    db: SVector<f32, L2>,
     act: PhantomData<F>,
     optw: <O as OptimizerFactory<L2, L1>>::Optimizer,
     optb: <O as OptimizerFactory<L2, 1>>::Optimizer,
@@ -32,25 +36,23 @@ where
     O: OptimizerFactory<L2, L1> + OptimizerFactory<L2, 1>,
 {
     pub fn new() -> Self {
+        Self::with_init::<Uniform<1, 2>, Uniform<1, 2>>()
+    }
+
+    // W initialized by IW and b by IB
+    pub fn with_init<IW: Initializer, IB: Initializer>(
+    ) -> Self {
         let a = SVector::zeros();
-        let mut w = SMatrix::zeros();
-        let mut b = SVector::zeros();
+        let w = IW::init(L1, L2);
+        let b = IB::init(L1, L2);
         let z = SVector::zeros();
-
-        // randomize W and b
-        let mut rng = rand::thread_rng();
-        let uniform = rand_distr::Uniform::new(-0.5, 0.5);
-        for i in 0..L2 {
-            for j in 0..L1 {
-                w[(i, j)] = rng.sample(uniform);
-            }
-            b[i] = rng.sample(uniform);
-        }
+        let dw = SMatrix::zeros();
+        let db = SVector::zeros();
 
         let act = PhantomData;
         let optw = <O as OptimizerFactory<L2, L1>>::Optimizer::init();
         let optb =
-            <O as OptimizerFactory<L2, 1>>::Optimizer::init(
+            <O as OptimizerFactory<L2, 1>>::Optimizer::init_bias(
             );
 
         Self {
@@ -58,6 +60,8 @@ where
             w,
             b,
             z,
+            dw,
+            db,
             act,
             optw,
             optb,
@@ -81,10 +85,74 @@ where
     ) -> SVector<f32, L1> {
         g = deriv_all::<L2, 1, F>(&self.z)
             .component_mul(&g);
-        let dzdw = &g * self.a.transpose();
+        self.dw = &g * self.a.transpose();
+        self.db = g;
         let dzda = self.w.transpose();
-        self.optw.update_param(&mut self.w, &dzdw);
-        self.optb.update_param(&mut self.b, &g);
         dzda * g
     }
 }
+
+impl<const L1: usize, const L2: usize, F, O> Parameters
+    for Sequential<L1, L2, F, O>
+where
+    O: OptimizerFactory<L2, L1> + OptimizerFactory<L2, 1>,
+{
+    fn visit_params(
+        &mut self,
+        f: &mut dyn FnMut(&mut [f32], &mut [f32]),
+    ) {
+        f(self.w.as_mut_slice(), self.dw.as_mut_slice());
+        f(self.b.as_mut_slice(), self.db.as_mut_slice());
+    }
+
+    fn update_params_scaled(
+        &mut self,
+        lr_scale: f32,
+        weight_decay: f32,
+    ) {
+        self.optw.update_weight(
+            &mut self.w,
+            &self.dw,
+            lr_scale,
+            weight_decay,
+        );
+        self.optb.update_param_scaled(
+            &mut self.b,
+            &self.db,
+            lr_scale,
+        );
+    }
+
+    fn finish_params(&mut self) {
+        self.optw.finish(&mut self.w);
+        self.optb.finish(&mut self.b);
+    }
+}
+
+#[test]
+fn test_seeded_runs_are_identical() {
+    use crate::activation::relu::Relu;
+    use crate::optimizers::adam::AdamFactory;
+    use crate::rng;
+
+    type Layer = Sequential<
+        8,
+        5,
+        Relu,
+        AdamFactory<1, 100, 9, 10, 9, 10>,
+    >;
+
+    let run = || {
+        rng::reseed(42);
+        let mut layer = Layer::new();
+        let y = layer.ff(SVector::repeat(0.3));
+        layer.bp(y);
+        layer.update_params();
+        (layer.w, layer.b)
+    };
+
+    let (w1, b1) = run();
+    let (w2, b2) = run();
+    assert_eq!(w1, w2);
+    assert_eq!(b1, b2);
+}
@@ -0,0 +1,233 @@
+use nalgebra::SMatrix;
+use nalgebra::SVector;
+
+use super::dropout::Dropout;
+use super::Parameters;
+use super::Recurrent;
+
+// recurrent layers on top of each other, first reads the input
+// sequence and every layer of rest reads the hidden sequence of
+// the one below it, through dropout and optionally with a
+// residual connection (y = layer(dropout(h)) + h)
+pub struct Stacked<
+    const X: usize,
+    const H: usize,
+    const T: usize,
+    R0: Recurrent<X, H, T>,
+    R: Recurrent<H, H, T>,
+> {
+    first: R0,
+    rest: Vec<R>,
+    dropout: Vec<Dropout<H, T>>,
+    residual: bool,
+}
+
+impl<
+        const X: usize,
+        const H: usize,
+        const T: usize,
+        R0,
+        R,
+    > Stacked<X, H, T, R0, R>
+where
+    R0: Recurrent<X, H, T>,
+    R: Recurrent<H, H, T>,
+{
+    pub fn new(first: R0, rest: Vec<R>) -> Self {
+        let dropout =
+            rest.iter().map(|_| Dropout::new(0.)).collect();
+        let residual = false;
+        Self {
+            first,
+            rest,
+            dropout,
+            residual,
+        }
+    }
+
+    // dropout applied to the input of every layer but the first
+    pub fn with_dropout(mut self, p: f32) -> Self {
+        self.dropout = self
+            .rest
+            .iter()
+            .map(|_| Dropout::new(p))
+            .collect();
+        self
+    }
+
+    pub fn with_residual(mut self) -> Self {
+        self.residual = true;
+        self
+    }
+
+    // feedforward
+    pub fn ff(
+        &mut self,
+        x: [SVector<f32, X>; T],
+    ) -> [SVector<f32, H>; T] {
+        self.ff_masked(x, T)
+    }
+
+    // feedforward over the first len timesteps
+    pub fn ff_masked(
+        &mut self,
+        x: [SVector<f32, X>; T],
+        len: usize,
+    ) -> [SVector<f32, H>; T] {
+        let mut h = self.first.ff_masked(x, len);
+        for k in 0..self.rest.len() {
+            let input = self.dropout[k].ff(to_mat(h));
+            let out = self.rest[k]
+                .ff_masked(to_array(input), len);
+            for t in 0..T {
+                h[t] = if self.residual {
+                    out[t] + h[t]
+                } else {
+                    out[t]
+                };
+            }
+        }
+        h
+    }
+
+    // backprop
+    pub fn bp(
+        &mut self,
+        gy: [SVector<f32, H>; T],
+    ) -> [SVector<f32, X>; T] {
+        let mut g = gy;
+        for k in (0..self.rest.len()).rev() {
+            let gin = self.rest[k].bp(g);
+            let gin =
+                to_array(self.dropout[k].bp(to_mat(gin)));
+            for t in 0..T {
+                g[t] = if self.residual {
+                    gin[t] + g[t]
+                } else {
+                    gin[t]
+                };
+            }
+        }
+        self.first.bp(g)
+    }
+}
+
+impl<
+        const X: usize,
+        const H: usize,
+        const T: usize,
+        R0,
+        R,
+    > Parameters for Stacked<X, H, T, R0, R>
+where
+    R0: Recurrent<X, H, T>,
+    R: Recurrent<H, H, T>,
+{
+    fn visit_params(
+        &mut self,
+        f: &mut dyn FnMut(&mut [f32], &mut [f32]),
+    ) {
+        self.first.visit_params(f);
+        self.rest
+            .iter_mut()
+            .for_each(|r| r.visit_params(f));
+    }
+
+    fn update_params_scaled(
+        &mut self,
+        lr_scale: f32,
+        weight_decay: f32,
+    ) {
+        self.first
+            .update_params_scaled(lr_scale, weight_decay);
+        self.rest.iter_mut().for_each(|r| {
+            r.update_params_scaled(lr_scale, weight_decay)
+        });
+    }
+
+    fn finish_params(&mut self) {
+        self.first.finish_params();
+        self.rest
+            .iter_mut()
+            .for_each(|r| r.finish_params());
+    }
+
+    fn set_training(&mut self, training: bool) {
+        self.first.set_training(training);
+        self.rest
+            .iter_mut()
+            .for_each(|r| r.set_training(training));
+        self.dropout
+            .iter_mut()
+            .for_each(|d| d.set_training(training));
+    }
+}
+
+impl<
+        const X: usize,
+        const H: usize,
+        const T: usize,
+        R0,
+        R,
+    > Recurrent<X, H, T> for Stacked<X, H, T, R0, R>
+where
+    R0: Recurrent<X, H, T>,
+    R: Recurrent<H, H, T>,
+{
+    fn ff_masked(
+        &mut self,
+        x: [SVector<f32, X>; T],
+        len: usize,
+    ) -> [SVector<f32, H>; T] {
+        self.ff_masked(x, len)
+    }
+
+    fn reset_state(&mut self) {
+        self.first.reset_state();
+        self.rest.iter_mut().for_each(|r| r.reset_state());
+    }
+
+    fn is_stateful(&self) -> bool {
+        self.first.is_stateful()
+            && self.rest.iter().all(|r| r.is_stateful())
+    }
+
+    fn bp(
+        &mut self,
+        gy: [SVector<f32, H>; T],
+    ) -> [SVector<f32, X>; T] {
+        self.bp(gy)
+    }
+}
+
+fn to_mat<const H: usize, const T: usize>(
+    h: [SVector<f32, H>; T],
+) -> SMatrix<f32, H, T> {
+    SMatrix::from_columns(&h)
+}
+
+fn to_array<const H: usize, const T: usize>(
+    m: SMatrix<f32, H, T>,
+) -> [SVector<f32, H>; T] {
+    std::array::from_fn(|t| m.column(t).into())
+}
+
+#[test]
+fn test_stacked_gradients() {
+    use super::assert_recurrent_gradients;
+    use super::gru::Gru;
+    use super::lstm::Lstm;
+    use crate::optimizers::sgd::SgdFactory;
+
+    type O = SgdFactory<1, 1>;
+
+    crate::rng::reseed(13);
+    let mut stacked = Stacked::new(
+        Lstm::<2, 3, 4, 5, O>::new(),
+        vec![Gru::<3, 3, 4, 6, O>::new(), Gru::new()],
+    )
+    .with_dropout(0.5)
+    .with_residual();
+    // dropout is off outside of training
+    assert_recurrent_gradients(&mut stacked);
+}
@@ -0,0 +1,209 @@
+use std::marker::PhantomData;
+
+use nalgebra::SMatrix;
+use nalgebra::SVector;
+
+use super::Parameters;
+use super::Recurrent;
+use crate::activation::deriv_all;
+use crate::activation::func_all;
+use crate::activation::ActivationFunction;
+use crate::initializers::uniform::Uniform;
+use crate::initializers::Initializer;
+use crate::optimizers::Optimizer;
+use crate::optimizers::OptimizerFactory;
+
+// y_t = F(W x_t + b) at every timestep, with the same W and b
+pub struct TimeDense<
+    const X: usize,
+    const Y: usize,
+    const T: usize,
+    F,
+    O: OptimizerFactory<Y, X> + OptimizerFactory<Y, 1>,
+> {
+    x: [SVector<f32, X>; T],
+    z: [SVector<f32, Y>; T],
+    w: SMatrix<f32, Y, X>,
+    b: SVector<f32, Y>,
+    dw: SMatrix<f32, Y, X>,
+    db: SVector<f32, Y>,
+    act: PhantomData<F>,
+    optw: <O as OptimizerFactory<Y, X>>::Optimizer,
+    optb: <O as OptimizerFactory<Y, 1>>::Optimizer,
+}
+
+impl<
+        const X: usize,
+        const Y: usize,
+        const T: usize,
+        F,
+        O,
+    > TimeDense<X, Y, T, F, O>
+where
+    F: ActivationFunction,
+    O: OptimizerFactory<Y, X> + OptimizerFactory<Y, 1>,
+{
+    pub fn new() -> Self {
+        Self::with_init::<Uniform<1, 2>, Uniform<1, 2>>()
+    }
+
+    // W initialized by IW and b by IB
+    pub fn with_init<IW: Initializer, IB: Initializer>(
+    ) -> Self {
+        let x = [SVector::zeros(); T];
+        let z = [SVector::zeros(); T];
+        let w = IW::init(X, Y);
+        let b = IB::init(X, Y);
+        let dw = SMatrix::zeros();
+        let db = SVector::zeros();
+
+        let act = PhantomData;
+        let optw =
+            <O as OptimizerFactory<Y, X>>::Optimizer::init(
+            );
+        let optb =
+            <O as OptimizerFactory<Y, 1>>::Optimizer::init_bias(
+            );
+
+        Self {
+            x,
+            z,
+            w,
+            b,
+            dw,
+            db,
+            act,
+            optw,
+            optb,
+        }
+    }
+
+    // feedforward
+    pub fn ff(
+        &mut self,
+        x: [SVector<f32, X>; T],
+    ) -> [SVector<f32, Y>; T] {
+        self.x = x;
+        self.z = x.map(|xt| self.w * xt + self.b);
+        self.z.map(|zt| func_all::<Y, 1, F>(&zt))
+    }
+
+    // backprop
+    pub fn bp(
+        &mut self,
+        gy: [SVector<f32, Y>; T],
+    ) -> [SVector<f32, X>; T] {
+        let mut gx = [SVector::zeros(); T];
+        self.dw = SMatrix::zeros();
+        self.db = SVector::zeros();
+        for t in 0..T {
+            let g = deriv_all::<Y, 1, F>(&self.z[t])
+                .component_mul(&gy[t]);
+            self.dw += g * self.x[t].transpose();
+            self.db += g;
+            gx[t] = self.w.transpose() * g;
+        }
+        gx
+    }
+}
+
+impl<
+        const X: usize,
+        const Y: usize,
+        const T: usize,
+        F,
+        O,
+    > Default for TimeDense<X, Y, T, F, O>
+where
+    F: ActivationFunction,
+    O: OptimizerFactory<Y, X> + OptimizerFactory<Y, 1>,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<
+        const X: usize,
+        const Y: usize,
+        const T: usize,
+        F,
+        O,
+    > Recurrent<X, Y, T> for TimeDense<X, Y, T, F, O>
+where
+    F: ActivationFunction,
+    O: OptimizerFactory<Y, X> + OptimizerFactory<Y, 1>,
+{
+    // the timesteps are independent, so the padding only gets
+    // zeroed
+    fn ff_masked(
+        &mut self,
+        x: [SVector<f32, X>; T],
+        len: usize,
+    ) -> [SVector<f32, Y>; T] {
+        let mut y = self.ff(x);
+        y[len..].fill(SVector::zeros());
+        y
+    }
+
+    fn bp(
+        &mut self,
+        gy: [SVector<f32, Y>; T],
+    ) -> [SVector<f32, X>; T] {
+        self.bp(gy)
+    }
+}
+
+impl<
+        const X: usize,
+        const Y: usize,
+        const T: usize,
+        F,
+        O,
+    > Parameters for TimeDense<X, Y, T, F, O>
+where
+    O: OptimizerFactory<Y, X> + OptimizerFactory<Y, 1>,
+{
+    fn visit_params(
+        &mut self,
+        f: &mut dyn FnMut(&mut [f32], &mut [f32]),
+    ) {
+        f(self.w.as_mut_slice(), self.dw.as_mut_slice());
+        f(self.b.as_mut_slice(), self.db.as_mut_slice());
+    }
+
+    fn update_params_scaled(
+        &mut self,
+        lr_scale: f32,
+        weight_decay: f32,
+    ) {
+        self.optw.update_weight(
+            &mut self.w,
+            &self.dw,
+            lr_scale,
+            weight_decay,
+        );
+        self.optb.update_param_scaled(
+            &mut self.b,
+            &self.db,
+            lr_scale,
+        );
+    }
+
+    fn finish_params(&mut self) {
+        self.optw.finish(&mut self.w);
+        self.optb.finish(&mut self.b);
+    }
+}
+
+#[test]
+fn test_timedense_gradients() {
+    use super::assert_recurrent_gradients;
+    use crate::activation::tanh::Tanh;
+    use crate::optimizers::sgd::SgdFactory;
+
+    crate::rng::reseed(31);
+    let mut layer =
+        TimeDense::<3, 2, 4, Tanh, SgdFactory<1, 1>>::new();
+    assert_recurrent_gradients(&mut layer);
+}
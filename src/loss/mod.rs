@@ -2,6 +2,7 @@ use nalgebra::SVector;
 
 pub mod crossent;
 pub mod mse;
+pub mod seqcrossent;
 
 pub trait LossFunction<const N: usize> {
     fn func(
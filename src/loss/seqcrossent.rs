@@ -0,0 +1,68 @@
+use nalgebra::SVector;
+
+use crate::layers::softmax::Softmax;
+
+// softmax followed by cross entropy at each of the first len
+// timesteps, averaged over them. takes the logits of every tag
+// and the index of the right one, the padding after len is
+// ignored
+pub struct SeqCrossEntropy;
+
+impl SeqCrossEntropy {
+    pub fn func<const Y: usize, const T: usize>(
+        logits: &[SVector<f32, Y>; T],
+        tags: &[usize; T],
+        len: usize,
+    ) -> f32 {
+        -(0..len)
+            .map(|t| softmax(logits[t])[tags[t]].ln())
+            .sum::<f32>()
+            / len as f32
+    }
+
+    // the gradient for the logits is softmax - onehot
+    pub fn grad<const Y: usize, const T: usize>(
+        logits: &[SVector<f32, Y>; T],
+        tags: &[usize; T],
+        len: usize,
+    ) -> [SVector<f32, Y>; T] {
+        let mut g = [SVector::zeros(); T];
+        for t in 0..len {
+            g[t] = softmax(logits[t]);
+            g[t][tags[t]] -= 1.;
+            g[t] /= len as f32;
+        }
+        g
+    }
+}
+
+// shifted by the max logit so that exp does not overflow
+fn softmax<const Y: usize>(
+    z: SVector<f32, Y>,
+) -> SVector<f32, Y> {
+    Softmax::new().ff(z.add_scalar(-z.max()))
+}
+
+#[test]
+fn test_seq_crossent_grad() {
+    let logits: [SVector<f32, 3>; 4] =
+        std::array::from_fn(|t| {
+            SVector::from_fn(|i, _| {
+                ((t * 3 + i) as f32 * 0.9).sin()
+            })
+        });
+    let tags = [2, 0, 1, 1];
+    let g = SeqCrossEntropy::grad(&logits, &tags, 3);
+    assert_eq!(g[3].norm(), 0.);
+    for t in 0..4 {
+        for i in 0..3 {
+            let (mut lp, mut lm) = (logits, logits);
+            lp[t][i] += 1e-2;
+            lm[t][i] -= 1e-2;
+            let fd = (SeqCrossEntropy::func(&lp, &tags, 3)
+                - SeqCrossEntropy::func(&lm, &tags, 3))
+                / 2e-2;
+            assert!((fd - g[t][i]).abs() < 1e-3);
+        }
+    }
+}
@@ -1,26 +1,43 @@
 #![feature(maybe_uninit_uninit_array, maybe_uninit_slice)]
 
 use runners::annrun::train_and_validate_csv_ann;
+use runners::annrun::train_and_validate_csv_ann_lbfgs;
+use runners::charlmrun::train_and_validate_text_charlm;
 use runners::cnnrun::train_and_validate_mnist_cnn;
 use runners::rnnrun::train_and_validate_imdb_rnn;
+use runners::seq2seqrun::train_and_validate_reversal_seq2seq;
+use runners::tagrun::train_and_validate_conll_tagger;
 
 pub mod activation;
 pub mod dataset;
+pub mod initializers;
 pub mod layers;
 pub mod loss;
 pub mod models;
 pub mod optimizers;
+pub mod rng;
 pub mod runners;
 
 fn main() {
-    let cmd = std::env::args()
-        .skip(1)
+    let mut args = std::env::args().skip(1);
+    let cmd =
+        args.next().expect("No CLI argument supplied");
+    let seed = args
         .next()
-        .expect("No CLI argument supplied");
+        .map(|seed| {
+            seed.parse().expect("Seed must be a u64")
+        })
+        .unwrap_or_else(rand::random);
+    println!("Seed: {seed}");
+    rng::set_seed(seed);
     match cmd.as_str() {
         "ann" => train_and_validate_csv_ann(),
+        "ann-lbfgs" => train_and_validate_csv_ann_lbfgs(),
         "cnn" => train_and_validate_mnist_cnn(),
         "rnn" => train_and_validate_imdb_rnn(),
+        "tag" => train_and_validate_conll_tagger(),
+        "seq2seq" => train_and_validate_reversal_seq2seq(),
+        "charlm" => train_and_validate_text_charlm(),
         _ => eprintln!("Invalid cmd provided"),
     };
 }
@@ -5,6 +5,8 @@ use nalgebra::SVector;
 use super::NeuralNetwork;
 use crate::activation::ActivationFunction;
 use crate::layers::dense::Dense;
+use crate::layers::Layers;
+use crate::layers::Parameters;
 use crate::loss::LossFunction;
 use crate::optimizers::OptimizerFactory;
 
@@ -74,3 +76,29 @@ where
         LOSS::func(y_out.clone(), y_test.clone())
     }
 }
+
+impl<
+        const X: usize,
+        const Y: usize,
+        const H: usize,
+        const L: usize,
+        F,
+        LOSS,
+        O,
+    > Layers for Ann<X, Y, H, L, F, LOSS, O>
+where
+    F: ActivationFunction,
+    LOSS: LossFunction<Y>,
+    O: OptimizerFactory<H, X>
+        + OptimizerFactory<H, 1>
+        + OptimizerFactory<H, H>
+        + OptimizerFactory<Y, H>
+        + OptimizerFactory<Y, 1>,
+{
+    fn visit_layers(
+        &mut self,
+        f: &mut dyn FnMut(&str, &mut dyn Parameters),
+    ) {
+        f("dense", &mut self.dense);
+    }
+}
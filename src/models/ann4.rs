@@ -7,6 +7,8 @@ use crate::activation::noact::NoActivation;
 use crate::activation::ActivationFunction;
 use crate::layers::sequential::Sequential;
 use crate::layers::softmax::Softmax;
+use crate::layers::Layers;
+use crate::layers::Parameters;
 use crate::loss::LossFunction;
 use crate::optimizers::OptimizerFactory;
 
@@ -103,6 +105,37 @@ where
     }
 }
 
+impl<
+        const L1: usize,
+        const L2: usize,
+        const L3: usize,
+        const L4: usize,
+        F1,
+        F2,
+        LOSS,
+        OPT,
+    > Layers for Ann4<L1, L2, L3, L4, F1, F2, LOSS, OPT>
+where
+    F1: ActivationFunction,
+    F2: ActivationFunction,
+    LOSS: LossFunction<L4>,
+    OPT: OptimizerFactory<L2, L1>
+        + OptimizerFactory<L2, 1>
+        + OptimizerFactory<L3, L2>
+        + OptimizerFactory<L3, 1>
+        + OptimizerFactory<L4, L3>
+        + OptimizerFactory<L4, 1>,
+{
+    fn visit_layers(
+        &mut self,
+        f: &mut dyn FnMut(&str, &mut dyn Parameters),
+    ) {
+        f("s1", &mut self.s1);
+        f("s2", &mut self.s2);
+        f("s3", &mut self.s3);
+    }
+}
+
 impl<
         const L1: usize,
         const L2: usize,
@@ -0,0 +1,151 @@
+use std::marker::PhantomData;
+
+use nalgebra::SVector;
+
+use super::NeuralNetwork;
+use crate::activation::noact::NoActivation;
+use crate::activation::ParametricActivation;
+use crate::layers::paramactlayer::ParamActivationLayer;
+use crate::layers::sequential::Sequential;
+use crate::layers::softmax::Softmax;
+use crate::layers::Layers;
+use crate::layers::Parameters;
+use crate::loss::LossFunction;
+use crate::optimizers::OptimizerFactory;
+
+// Ann4 with the activations of the hidden layers trained too, e.g.
+// the slopes of PRelu or the betas of Swish
+pub struct AnnParamAct<
+    const L1: usize,
+    const L2: usize,
+    const L3: usize,
+    const L4: usize,
+    P1,
+    P2,
+    LOSS,
+    OPT: OptimizerFactory<L2, L1>
+        + OptimizerFactory<L2, 1>
+        + OptimizerFactory<L3, L2>
+        + OptimizerFactory<L3, 1>
+        + OptimizerFactory<L4, L3>
+        + OptimizerFactory<L4, 1>,
+> {
+    s1: Sequential<L1, L2, NoActivation, OPT>,
+    a1: ParamActivationLayer<L2, P1, OPT>,
+    s2: Sequential<L2, L3, NoActivation, OPT>,
+    a2: ParamActivationLayer<L3, P2, OPT>,
+    s3: Sequential<L3, L4, NoActivation, OPT>,
+    softmax: Softmax<L4>,
+    loss: PhantomData<LOSS>,
+}
+
+impl<
+        const L1: usize,
+        const L2: usize,
+        const L3: usize,
+        const L4: usize,
+        P1,
+        P2,
+        LOSS,
+        OPT,
+    > NeuralNetwork<L4>
+    for AnnParamAct<L1, L2, L3, L4, P1, P2, LOSS, OPT>
+where
+    P1: ParametricActivation,
+    P2: ParametricActivation,
+    LOSS: LossFunction<L4>,
+    OPT: OptimizerFactory<L2, L1>
+        + OptimizerFactory<L2, 1>
+        + OptimizerFactory<L3, L2>
+        + OptimizerFactory<L3, 1>
+        + OptimizerFactory<L4, L3>
+        + OptimizerFactory<L4, 1>,
+{
+    type ModelInput = SVector<f32, L1>;
+
+    fn new() -> Self {
+        let s1 = Sequential::new();
+        let a1 = ParamActivationLayer::new();
+        let s2 = Sequential::new();
+        let a2 = ParamActivationLayer::new();
+        let s3 = Sequential::new();
+        let softmax = Softmax::new();
+        let loss = PhantomData;
+        Self {
+            s1,
+            a1,
+            s2,
+            a2,
+            s3,
+            softmax,
+            loss,
+        }
+    }
+
+    fn feedforward(
+        &mut self,
+        x: Self::ModelInput,
+    ) -> SVector<f32, L4> {
+        let a = self.s1.ff(x);
+        let a = self.a1.ff(a);
+        let a = self.s2.ff(a);
+        let a = self.a2.ff(a);
+        let a = self.s3.ff(a);
+        self.softmax.ff(a)
+    }
+
+    fn backprop(
+        &mut self,
+        y_out: SVector<f32, L4>,
+        y_test: SVector<f32, L4>,
+    ) {
+        let g = LOSS::grad(y_out, y_test);
+        let g = self.softmax.bp(g);
+        let g = self.s3.bp(g);
+        let g = self.a2.bp(g);
+        let g = self.s2.bp(g);
+        let g = self.a1.bp(g);
+        self.s1.bp(g);
+    }
+
+    fn loss(
+        y_out: &SVector<f32, L4>,
+        y_test: &SVector<f32, L4>,
+    ) -> f32 {
+        LOSS::func(*y_out, *y_test)
+    }
+}
+
+impl<
+        const L1: usize,
+        const L2: usize,
+        const L3: usize,
+        const L4: usize,
+        P1,
+        P2,
+        LOSS,
+        OPT,
+    > Layers
+    for AnnParamAct<L1, L2, L3, L4, P1, P2, LOSS, OPT>
+where
+    P1: ParametricActivation,
+    P2: ParametricActivation,
+    LOSS: LossFunction<L4>,
+    OPT: OptimizerFactory<L2, L1>
+        + OptimizerFactory<L2, 1>
+        + OptimizerFactory<L3, L2>
+        + OptimizerFactory<L3, 1>
+        + OptimizerFactory<L4, L3>
+        + OptimizerFactory<L4, 1>,
+{
+    fn visit_layers(
+        &mut self,
+        f: &mut dyn FnMut(&str, &mut dyn Parameters),
+    ) {
+        f("s1", &mut self.s1);
+        f("a1", &mut self.a1);
+        f("s2", &mut self.s2);
+        f("a2", &mut self.a2);
+        f("s3", &mut self.s3);
+    }
+}
@@ -0,0 +1,174 @@
+use nalgebra::SVector;
+
+// a model generating one token at a time
+pub trait Decoder<const V: usize> {
+    // logits of the token after prefix
+    fn step(&mut self, prefix: &[usize])
+        -> SVector<f32, V>;
+}
+
+// any closure from a prefix to the logits of the next token
+impl<F, const V: usize> Decoder<V> for F
+where
+    F: FnMut(&[usize]) -> SVector<f32, V>,
+{
+    fn step(
+        &mut self,
+        prefix: &[usize],
+    ) -> SVector<f32, V> {
+        self(prefix)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Hypothesis {
+    // without the start and the end of sequence tokens
+    pub tokens: Vec<usize>,
+    // log probability divided by length^alpha
+    pub score: f32,
+}
+
+// keeps the width most likely prefixes at every step. a prefix
+// ending with eos is finished, the search stops when width
+// prefixes are finished or after max_len tokens
+#[derive(Clone, Copy, Debug)]
+pub struct BeamSearch {
+    pub width: usize,
+    pub max_len: usize,
+    pub eos: Option<usize>,
+    // 0 ranks by log probability, which favours short outputs, 1
+    // by log probability per token
+    pub alpha: f32,
+}
+
+impl BeamSearch {
+    pub fn new(width: usize, max_len: usize) -> Self {
+        assert!(width > 0);
+        Self {
+            width,
+            max_len,
+            eos: None,
+            alpha: 0.,
+        }
+    }
+
+    pub fn eos(mut self, eos: usize) -> Self {
+        self.eos = Some(eos);
+        self
+    }
+
+    pub fn length_penalty(mut self, alpha: f32) -> Self {
+        self.alpha = alpha;
+        self
+    }
+
+    // the hypotheses continuing start, best first
+    pub fn search<
+        const V: usize,
+        D: Decoder<V> + ?Sized,
+    >(
+        &self,
+        model: &mut D,
+        start: &[usize],
+    ) -> Vec<Hypothesis> {
+        let mut beams = vec![(start.to_vec(), 0.)];
+        let mut finished = Vec::new();
+        for _ in 0..self.max_len {
+            let mut candidates = Vec::new();
+            for (prefix, logp) in &beams {
+                let logits = model.step(prefix);
+                let next = log_softmax(&logits);
+                for (token, &l) in next.iter().enumerate() {
+                    candidates.push((
+                        prefix,
+                        token,
+                        logp + l,
+                    ));
+                }
+            }
+            candidates.sort_by(|a, b| b.2.total_cmp(&a.2));
+
+            let mut next_beams = Vec::new();
+            for (prefix, token, logp) in candidates {
+                if next_beams.len() + finished.len()
+                    >= self.width
+                {
+                    break;
+                }
+                let tokens = &prefix[start.len()..];
+                if Some(token) == self.eos {
+                    // the end counts in the length
+                    let len = tokens.len() + 1;
+                    finished.push(
+                        self.finish(tokens, logp, len),
+                    );
+                } else {
+                    let mut tokens = prefix.clone();
+                    tokens.push(token);
+                    next_beams.push((tokens, logp));
+                }
+            }
+            beams = next_beams;
+            if beams.is_empty() {
+                break;
+            }
+        }
+        // out of steps before eos
+        for (tokens, logp) in beams {
+            let tokens = &tokens[start.len()..];
+            finished.push(self.finish(
+                tokens,
+                logp,
+                tokens.len(),
+            ));
+        }
+        finished
+            .sort_by(|a, b| b.score.total_cmp(&a.score));
+        finished
+    }
+
+    fn finish(
+        &self,
+        tokens: &[usize],
+        logp: f32,
+        len: usize,
+    ) -> Hypothesis {
+        let tokens = tokens.to_vec();
+        let score = logp / (len as f32).powf(self.alpha);
+        Hypothesis { tokens, score }
+    }
+}
+
+// shifted by the max logit so that exp does not overflow
+fn log_softmax<const V: usize>(
+    z: &SVector<f32, V>,
+) -> SVector<f32, V> {
+    let z = z.add_scalar(-z.max());
+    let log_sum = z.map(f32::exp).sum().ln();
+    z.add_scalar(-log_sum)
+}
+
+#[test]
+fn test_beam_search() {
+    // token 0 looks best first but every continuation of it is
+    // unlikely, while 1 is almost always followed by the end (2)
+    let mut model = |prefix: &[usize]| {
+        let p: [f32; 3] = match prefix {
+            [] => [0.6, 0.4, 0.],
+            [0] => [0.3, 0.3, 0.4],
+            [1] => [0.05, 0.05, 0.9],
+            _ => [0., 0., 1.],
+        };
+        SVector::<f32, 3>::from_fn(|i, _| p[i].ln())
+    };
+    let greedy = BeamSearch::new(1, 5)
+        .eos(2)
+        .search(&mut model, &[]);
+    assert_eq!(greedy[0].tokens, [0]);
+    let beam = BeamSearch::new(2, 5)
+        .eos(2)
+        .search(&mut model, &[]);
+    assert_eq!(beam.len(), 2);
+    assert_eq!(beam[0].tokens, [1]);
+    assert!((beam[0].score - 0.36f32.ln()).abs() < 1e-5);
+}
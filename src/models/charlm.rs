@@ -0,0 +1,270 @@
+use nalgebra::SVector;
+
+use super::sampling::Sampler;
+use crate::activation::noact::NoActivation;
+use crate::layers::timedense::TimeDense;
+use crate::layers::Layers;
+use crate::layers::Parameters;
+use crate::layers::Recurrent;
+use crate::loss::seqcrossent::SeqCrossEntropy;
+use crate::optimizers::OptimizerFactory;
+
+// most distinct characters a text can have
+pub const V: usize = 100;
+// characters per training window
+pub const T: usize = 32;
+pub const H: usize = 128;
+
+// characters of a text sorted, their position is the token
+pub struct CharVocab {
+    chars: Vec<char>,
+}
+
+impl CharVocab {
+    pub fn new(text: &str) -> Self {
+        let mut chars = text.chars().collect::<Vec<_>>();
+        chars.sort_unstable();
+        chars.dedup();
+        assert!(
+            chars.len() <= V,
+            "Found {} characters but the model has {V}",
+            chars.len()
+        );
+        Self { chars }
+    }
+
+    // characters missing from the vocabulary are dropped
+    pub fn encode(&self, text: &str) -> Vec<usize> {
+        text.chars()
+            .filter_map(|c| {
+                self.chars.binary_search(&c).ok()
+            })
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.chars.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chars.is_empty()
+    }
+
+    pub fn decode(&self, tokens: &[usize]) -> String {
+        tokens.iter().map(|&i| self.chars[i]).collect()
+    }
+}
+
+// predicts the character after each character of a window. the
+// text is read in consecutive windows, so R has to be stateful
+// for the state to carry over from one to the next
+pub struct CharLm<
+    R: Recurrent<V, H, T>,
+    O: OptimizerFactory<V, H> + OptimizerFactory<V, 1>,
+> {
+    rnn: R,
+    // tokens actually used, the logits of the others are never
+    // sampled
+    chars: usize,
+    out: TimeDense<H, V, T, NoActivation, O>,
+}
+
+impl<R, O> CharLm<R, O>
+where
+    R: Recurrent<V, H, T>,
+    O: OptimizerFactory<V, H> + OptimizerFactory<V, 1>,
+{
+    pub fn new(rnn: R, chars: usize) -> Self {
+        assert!(chars <= V);
+        let out = TimeDense::new();
+        Self { rnn, chars, out }
+    }
+
+    // back to the start of a text
+    pub fn reset_state(&mut self) {
+        self.rnn.reset_state();
+    }
+
+    // logits of the character after each of x
+    pub fn feedforward(
+        &mut self,
+        x: &[usize],
+    ) -> [SVector<f32, V>; T] {
+        assert!(!x.is_empty() && x.len() <= T);
+        let mut xs = [SVector::zeros(); T];
+        for (t, &c) in x.iter().take(T).enumerate() {
+            xs[t][c] = 1.;
+        }
+        let h = self.rnn.ff_masked(xs, x.len());
+        self.out.ff(h)
+    }
+
+    // computes the gradients, update_params applies them
+    pub fn backprop(&mut self, g: [SVector<f32, V>; T]) {
+        let g = self.out.bp(g);
+        self.rnn.bp(g);
+    }
+
+    // mean loss over the window, with next[t] the character
+    // after x[t]
+    pub fn train_step(
+        &mut self,
+        x: &[usize],
+        next: &[usize],
+    ) -> f32 {
+        let (logits, tags) = self.window(x, next);
+        self.backprop(SeqCrossEntropy::grad(
+            &logits,
+            &tags,
+            x.len(),
+        ));
+        SeqCrossEntropy::func(&logits, &tags, x.len())
+    }
+
+    // mean loss per character (in nats) over a whole text, read
+    // from a zero state
+    pub fn loss(&mut self, text: &[usize]) -> f32 {
+        assert!(
+            text.len() >= 2,
+            "A text needs at least 2 characters"
+        );
+        self.reset_state();
+        let mut sum = 0.;
+        for start in (0..text.len() - 1).step_by(T) {
+            let end = (start + T).min(text.len() - 1);
+            let x = &text[start..end];
+            let (logits, tags) =
+                self.window(x, &text[start + 1..end + 1]);
+            sum += SeqCrossEntropy::func(
+                &logits,
+                &tags,
+                x.len(),
+            ) * x.len() as f32;
+        }
+        sum / (text.len() - 1) as f32
+    }
+
+    // continues the prompt with n sampled characters. the prompt
+    // is read window by window and then every new character is fed
+    // back alone
+    pub fn generate(
+        &mut self,
+        prompt: &[usize],
+        n: usize,
+        sampler: &Sampler,
+    ) -> Vec<usize> {
+        assert!(!prompt.is_empty());
+        // a stateless rnn would forget everything before the last
+        // character
+        assert!(
+            self.rnn.is_stateful(),
+            "Generating needs a stateful rnn"
+        );
+        self.reset_state();
+        let mut logits = SVector::zeros();
+        for chunk in prompt.chunks(T) {
+            logits =
+                self.feedforward(chunk)[chunk.len() - 1];
+        }
+        let mut out = Vec::with_capacity(n);
+        for _ in 0..n {
+            logits
+                .rows_mut(self.chars, V - self.chars)
+                .fill(f32::NEG_INFINITY);
+            let c = sampler.sample(&logits);
+            out.push(c);
+            logits = self.feedforward(&[c])[0];
+        }
+        out
+    }
+
+    fn window(
+        &mut self,
+        x: &[usize],
+        next: &[usize],
+    ) -> ([SVector<f32, V>; T], [usize; T]) {
+        assert!(
+            !x.is_empty()
+                && x.len() <= T
+                && next.len() == x.len()
+        );
+        let mut tags = [0; T];
+        tags[..next.len()].copy_from_slice(next);
+        (self.feedforward(x), tags)
+    }
+}
+
+impl<R, O> Layers for CharLm<R, O>
+where
+    R: Recurrent<V, H, T>,
+    O: OptimizerFactory<V, H> + OptimizerFactory<V, 1>,
+{
+    fn visit_layers(
+        &mut self,
+        f: &mut dyn FnMut(&str, &mut dyn Parameters),
+    ) {
+        f("rnn", &mut self.rnn);
+        f("out", &mut self.out);
+    }
+}
+
+#[test]
+fn test_charlm() {
+    use crate::activation::tanh::Tanh;
+    use crate::initializers::constant::Zeros;
+    use crate::initializers::orthogonal::Orthogonal;
+    use crate::initializers::xavier::XavierUniform;
+    use crate::layers::rnncell::RnnCell;
+    use crate::optimizers::adam::AdamFactory;
+
+    type O = AdamFactory<1, 100, 9, 10, 999, 1000>;
+
+    // the model lives on the stack
+    std::thread::Builder::new()
+        .stack_size(64 * 1024 * 1024)
+        .spawn(|| {
+            crate::rng::reseed(6);
+            let vocab = CharVocab::new("abc");
+            let text = vocab.encode(&"abcab".repeat(8));
+            let rnn = RnnCell::<
+                V,
+                H,
+                H,
+                T,
+                O,
+                Tanh,
+                false,
+            >::with_init::<
+                XavierUniform,
+                Orthogonal,
+                Zeros,
+            >()
+            .stateful();
+            let mut model =
+                CharLm::<_, O>::new(rnn, vocab.len());
+            let before = model.loss(&text);
+            for _ in 0..10 {
+                model.reset_state();
+                for start in (0..text.len() - 1).step_by(T)
+                {
+                    let end =
+                        (start + T).min(text.len() - 1);
+                    model.train_step(
+                        &text[start..end],
+                        &text[start + 1..end + 1],
+                    );
+                    model.update_params();
+                }
+            }
+            assert!(model.loss(&text) < 0.5 * before);
+
+            // only the 3 characters of the text are sampled
+            let sampler = Sampler::new().temperature(2.);
+            let out =
+                model.generate(&text[..3], 100, &sampler);
+            assert!(out.iter().all(|&c| c < vocab.len()));
+        })
+        .unwrap()
+        .join()
+        .unwrap();
+}
@@ -7,6 +7,8 @@ use crate::layers::conv::Conv2d;
 use crate::layers::dense::Dense;
 use crate::layers::maxpool::MaxPool2d;
 use crate::layers::relu2d::Relu2dLayer;
+use crate::layers::Layers;
+use crate::layers::Parameters;
 use crate::loss::crossent::CrossEntropy;
 use crate::loss::LossFunction;
 use crate::optimizers::OptimizerFactory;
@@ -146,6 +148,28 @@ where
     }
 }
 
+impl<OPT> Layers for MyCnn<OPT>
+where
+    OPT: OptimizerFactory<CONV_WEIGHT_DIM, CONV_WEIGHT_DIM>
+        + OptimizerFactory<
+            HIDDEN_LAYER_DIM,
+            SEQ_LAYER_INITIAL_DIM,
+        > + OptimizerFactory<HIDDEN_LAYER_DIM, 1>
+        + OptimizerFactory<HIDDEN_LAYER_DIM, HIDDEN_LAYER_DIM>
+        + OptimizerFactory<DIGITS, HIDDEN_LAYER_DIM>
+        + OptimizerFactory<DIGITS, 1>,
+{
+    fn visit_layers(
+        &mut self,
+        f: &mut dyn FnMut(&str, &mut dyn Parameters),
+    ) {
+        for (i, conv) in self.conv.iter_mut().enumerate() {
+            f(&format!("conv{i}"), conv);
+        }
+        f("dense", &mut self.dense);
+    }
+}
+
 fn flatten(
     v: [SMatrix<f32, POST_POOL_DIM, POST_POOL_DIM>;
         NUM_CONV],
@@ -11,6 +11,8 @@ use crate::layers::maxpool::MaxPool2d;
 use crate::layers::relu2d::Relu2dLayer;
 use crate::layers::sequential::Sequential;
 use crate::layers::softmax::Softmax;
+use crate::layers::Layers;
+use crate::layers::Parameters;
 use crate::loss::crossent::CrossEntropy;
 use crate::loss::LossFunction;
 use crate::optimizers::OptimizerFactory;
@@ -190,6 +192,32 @@ where
     }
 }
 
+impl<OPT> Layers for MyCnn2<OPT>
+where
+    OPT: OptimizerFactory<100, SEQ_LAYER_INITIAL_DIM>
+        + OptimizerFactory<100, 1>
+        + OptimizerFactory<50, 100>
+        + OptimizerFactory<50, 1>
+        + OptimizerFactory<20, 50>
+        + OptimizerFactory<20, 1>
+        + OptimizerFactory<DIGITS, 20>
+        + OptimizerFactory<DIGITS, 1>
+        + OptimizerFactory<CONV1_WEIGHT_DIM, CONV1_WEIGHT_DIM>
+        + OptimizerFactory<CONV2_WEIGHT_DIM, CONV2_WEIGHT_DIM>,
+{
+    fn visit_layers(
+        &mut self,
+        f: &mut dyn FnMut(&str, &mut dyn Parameters),
+    ) {
+        f("conv1", &mut self.conv1);
+        f("conv2", &mut self.conv2);
+        f("s1", &mut self.s1);
+        f("s2", &mut self.s2);
+        f("s3", &mut self.s3);
+        f("s4", &mut self.s4);
+    }
+}
+
 fn flatten(
     v: SMatrix<f32, POST_POOL2_DIM, POST_POOL2_DIM>,
 ) -> SVector<f32, SEQ_LAYER_INITIAL_DIM> {
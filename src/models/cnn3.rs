@@ -11,6 +11,8 @@ use crate::layers::maxpool::MaxPool2d;
 use crate::layers::relu2d::Relu2dLayer;
 use crate::layers::seq2d::Dense2D;
 use crate::layers::softmax::Softmax;
+use crate::layers::Layers;
+use crate::layers::Parameters;
 use crate::loss::crossent::CrossEntropy;
 use crate::loss::LossFunction;
 use crate::optimizers::OptimizerFactory;
@@ -181,6 +183,28 @@ where
     }
 }
 
+impl<OPT> Layers for MyCnn3<OPT>
+where
+    OPT: OptimizerFactory<CONV1_WEIGHT_DIM, CONV1_WEIGHT_DIM>
+        + OptimizerFactory<CONV2_WEIGHT_DIM, CONV2_WEIGHT_DIM>
+        + OptimizerFactory<6, 8>
+        + OptimizerFactory<5, 8>
+        + OptimizerFactory<5, 6>
+        + OptimizerFactory<2, 6>
+        + OptimizerFactory<2, 5>,
+{
+    fn visit_layers(
+        &mut self,
+        f: &mut dyn FnMut(&str, &mut dyn Parameters),
+    ) {
+        f("conv1", &mut self.conv1);
+        f("conv2", &mut self.conv2);
+        f("s1", &mut self.s1);
+        f("s2", &mut self.s2);
+        f("s3", &mut self.s3);
+    }
+}
+
 fn flatten(v: SMatrix<f32, 2, 5>) -> SVector<f32, 10> {
     let mut out = SVector::zeros();
     for i in 0..2 {
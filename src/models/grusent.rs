@@ -0,0 +1,113 @@
+use nalgebra::SMatrix;
+use nalgebra::SVector;
+
+use super::NeuralNetwork;
+use crate::activation::relu::Relu;
+use crate::layers::dense::Dense;
+use crate::layers::gru::Gru;
+use crate::layers::masked::Masked;
+use crate::layers::Layers;
+use crate::layers::Parameters;
+use crate::loss::crossent::CrossEntropy;
+use crate::loss::LossFunction;
+use crate::optimizers::OptimizerFactory;
+
+const N: usize = 50;
+const M: usize = 200;
+const G: usize = 100;
+const GM: usize = G + M;
+const L: usize = 40;
+
+pub struct GruSentAnalyzer<
+    O: OptimizerFactory<L, G>
+        + OptimizerFactory<L, 1>
+        + OptimizerFactory<L, L>
+        + OptimizerFactory<2, L>
+        + OptimizerFactory<2, 1>
+        + OptimizerFactory<G, GM>
+        + OptimizerFactory<G, 1>,
+> {
+    gru: Gru<M, G, N, GM, O>,
+    dense: Dense<G, 2, L, 5, Relu, O>,
+    // tokens of the last input
+    len: usize,
+}
+
+impl<O> NeuralNetwork<2> for GruSentAnalyzer<O>
+where
+    O: OptimizerFactory<L, G>
+        + OptimizerFactory<L, 1>
+        + OptimizerFactory<L, L>
+        + OptimizerFactory<2, L>
+        + OptimizerFactory<2, 1>
+        + OptimizerFactory<G, GM>
+        + OptimizerFactory<G, 1>,
+{
+    type ModelInput = Masked<N, M>;
+
+    fn new() -> Self {
+        let gru = Gru::new();
+        let dense = Dense::new();
+        let len = N;
+        Self { gru, dense, len }
+    }
+
+    fn feedforward(
+        &mut self,
+        x: Self::ModelInput,
+    ) -> SVector<f32, 2> {
+        self.len = x.len;
+        let x = mat_to_array(x.x);
+        let x = self.gru.ff_masked(x, self.len);
+        // state after the last token, not after the padding
+        self.dense.ff(x[self.len - 1])
+    }
+
+    fn backprop(
+        &mut self,
+        y_out: SVector<f32, 2>,
+        y_test: SVector<f32, 2>,
+    ) {
+        let g = CrossEntropy::grad(y_out, y_test);
+        let g = self.dense.bp(g);
+        let mut garr = [SVector::zeros(); N];
+        garr[self.len - 1] = g;
+        self.gru.bp(garr);
+    }
+
+    fn loss(
+        y_out: &SVector<f32, 2>,
+        y_test: &SVector<f32, 2>,
+    ) -> f32 {
+        CrossEntropy::func(*y_out, *y_test)
+    }
+}
+
+impl<O> Layers for GruSentAnalyzer<O>
+where
+    O: OptimizerFactory<L, G>
+        + OptimizerFactory<L, 1>
+        + OptimizerFactory<L, L>
+        + OptimizerFactory<2, L>
+        + OptimizerFactory<2, 1>
+        + OptimizerFactory<G, GM>
+        + OptimizerFactory<G, 1>,
+{
+    fn visit_layers(
+        &mut self,
+        f: &mut dyn FnMut(&str, &mut dyn Parameters),
+    ) {
+        f("gru", &mut self.gru);
+        f("dense", &mut self.dense);
+    }
+}
+
+fn mat_to_array<const N: usize, const X: usize>(
+    m: SMatrix<f32, N, X>,
+) -> [SVector<f32, X>; N] {
+    let mut out = [SVector::zeros(); N];
+    m.row_iter().enumerate().for_each(|(i, row)| {
+        out[i] = row.transpose();
+    });
+    out
+}
@@ -3,8 +3,15 @@ use nalgebra::SVector;
 
 use super::NeuralNetwork;
 use crate::activation::relu::Relu;
+use crate::initializers::constant::Constant;
+use crate::initializers::uniform::Uniform;
+use crate::layers::bidirectional::Bidirectional;
+use crate::layers::bidirectional::Concat;
 use crate::layers::dense::Dense;
 use crate::layers::lstm::Lstm;
+use crate::layers::masked::Masked;
+use crate::layers::Layers;
+use crate::layers::Parameters;
 use crate::loss::crossent::CrossEntropy;
 use crate::loss::LossFunction;
 use crate::optimizers::OptimizerFactory;
@@ -15,7 +22,7 @@ const MM: usize = 2 * M;
 const L: usize = 40;
 
 pub struct LstmSentAnalyzer<
-    O: OptimizerFactory<L, M>
+    O: OptimizerFactory<L, MM>
         + OptimizerFactory<L, 1>
         + OptimizerFactory<L, L>
         + OptimizerFactory<2, L>
@@ -23,13 +30,24 @@ pub struct LstmSentAnalyzer<
         + OptimizerFactory<M, MM>
         + OptimizerFactory<M, 1>,
 > {
-    lstm: Lstm<M, M, N, MM, O>,
-    dense: Dense<M, 2, L, 5, Relu, O>,
+    // both directions concatenated, MM is also the size of the
+    // [h, x] the lstm gates see
+    lstm: Bidirectional<
+        M,
+        M,
+        MM,
+        N,
+        Lstm<M, M, N, MM, O>,
+        Concat,
+    >,
+    dense: Dense<MM, 2, L, 5, Relu, O>,
+    // tokens in the last input
+    len: usize,
 }
 
 impl<O> NeuralNetwork<2> for LstmSentAnalyzer<O>
 where
-    O: OptimizerFactory<L, M>
+    O: OptimizerFactory<L, MM>
         + OptimizerFactory<L, 1>
         + OptimizerFactory<L, L>
         + OptimizerFactory<2, L>
@@ -37,21 +55,36 @@ where
         + OptimizerFactory<M, MM>
         + OptimizerFactory<M, 1>,
 {
-    type ModelInput = SMatrix<f32, N, M>;
+    type ModelInput = Masked<N, M>;
 
     fn new() -> Self {
-        let lstm = Lstm::new();
+        let lstm = || {
+            Lstm::with_init::<
+                Uniform<1, 2>,
+                Uniform<1, 2>,
+                Uniform<1, 2>,
+                Constant<1, 1>,
+            >()
+        };
+        let lstm = Bidirectional::new(lstm(), lstm());
         let dense = Dense::new();
-        Self { lstm, dense }
+        let len = N;
+        Self { lstm, dense, len }
     }
 
     fn feedforward(
         &mut self,
         x: Self::ModelInput,
     ) -> SVector<f32, 2> {
-        let x = mat_to_array(x);
-        let x = self.lstm.ff(x);
-        let x = x[N - 1];
+        self.len = x.len;
+        let x = mat_to_array(x.x);
+        let x = self.lstm.ff_masked(x, self.len);
+        // final state of each direction, the forward one is at
+        // the last token
+        let mut last = x[self.len - 1];
+        last.fixed_rows_mut::<M>(M)
+            .copy_from(&x[0].fixed_rows::<M>(M));
+        let x = last;
         let x = self.dense.ff(x);
         x
     }
@@ -63,8 +96,13 @@ where
     ) {
         let g = CrossEntropy::grad(y_out, y_test);
         let g = self.dense.bp(g);
-        let mut garr = [SMatrix::zeros(); N];
-        garr[N - 1] = g;
+        let mut garr = [SVector::<f32, MM>::zeros(); N];
+        garr[self.len - 1]
+            .fixed_rows_mut::<M>(0)
+            .copy_from(&g.fixed_rows::<M>(0));
+        garr[0]
+            .fixed_rows_mut::<M>(M)
+            .copy_from(&g.fixed_rows::<M>(M));
         self.lstm.bp(garr);
     }
 
@@ -76,6 +114,25 @@ where
     }
 }
 
+impl<O> Layers for LstmSentAnalyzer<O>
+where
+    O: OptimizerFactory<L, MM>
+        + OptimizerFactory<L, 1>
+        + OptimizerFactory<L, L>
+        + OptimizerFactory<2, L>
+        + OptimizerFactory<2, 1>
+        + OptimizerFactory<M, MM>
+        + OptimizerFactory<M, 1>,
+{
+    fn visit_layers(
+        &mut self,
+        f: &mut dyn FnMut(&str, &mut dyn Parameters),
+    ) {
+        f("lstm", &mut self.lstm);
+        f("dense", &mut self.dense);
+    }
+}
+
 fn mat_to_array<const N: usize, const X: usize>(
     m: SMatrix<f32, N, X>,
 ) -> [SVector<f32, X>; N] {
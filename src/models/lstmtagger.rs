@@ -0,0 +1,112 @@
+use nalgebra::SMatrix;
+use nalgebra::SVector;
+
+use super::SequenceTagger;
+use crate::activation::noact::NoActivation;
+use crate::initializers::constant::Constant;
+use crate::initializers::uniform::Uniform;
+use crate::layers::bidirectional::Bidirectional;
+use crate::layers::bidirectional::Concat;
+use crate::layers::lookup::Lookup;
+use crate::layers::lstm::Lstm;
+use crate::layers::timedense::TimeDense;
+use crate::layers::Layers;
+use crate::layers::Parameters;
+use crate::optimizers::OptimizerFactory;
+
+pub const N: usize = 40;
+pub const M: usize = 50;
+const H: usize = 50;
+const HH: usize = 2 * H;
+const HM: usize = H + M;
+
+// bidirectional lstm over trained word embeddings and a linear
+// layer giving the logits of the Y tags at every token
+pub struct LstmTagger<
+    const Y: usize,
+    O: OptimizerFactory<H, HM>
+        + OptimizerFactory<H, 1>
+        + OptimizerFactory<M, 1>
+        + OptimizerFactory<Y, HH>
+        + OptimizerFactory<Y, 1>,
+> {
+    embed: Lookup<N, M, O>,
+    lstm: Bidirectional<
+        M,
+        H,
+        HH,
+        N,
+        Lstm<M, H, N, HM, O>,
+        Concat,
+    >,
+    out: TimeDense<HH, Y, N, NoActivation, O>,
+}
+
+impl<const Y: usize, O> SequenceTagger<N, Y>
+    for LstmTagger<Y, O>
+where
+    O: OptimizerFactory<H, HM>
+        + OptimizerFactory<H, 1>
+        + OptimizerFactory<M, 1>
+        + OptimizerFactory<Y, HH>
+        + OptimizerFactory<Y, 1>,
+{
+    fn new(vocab: usize) -> Self {
+        let embed = Lookup::new(vocab);
+        let lstm = || {
+            Lstm::with_init::<
+                Uniform<1, 2>,
+                Uniform<1, 2>,
+                Uniform<1, 2>,
+                Constant<1, 1>,
+            >()
+        };
+        let lstm = Bidirectional::new(lstm(), lstm());
+        let out = TimeDense::new();
+        Self { embed, lstm, out }
+    }
+
+    fn feedforward(
+        &mut self,
+        tokens: &[usize],
+    ) -> [SVector<f32, Y>; N] {
+        let x = self.embed.ff(tokens);
+        let h =
+            self.lstm.ff_masked(mat_to_array(x.x), x.len);
+        self.out.ff(h)
+    }
+
+    fn backprop(&mut self, g: [SVector<f32, Y>; N]) {
+        let g = self.out.bp(g);
+        let g = self.lstm.bp(g);
+        self.embed.bp(g);
+    }
+}
+
+impl<const Y: usize, O> Layers for LstmTagger<Y, O>
+where
+    O: OptimizerFactory<H, HM>
+        + OptimizerFactory<H, 1>
+        + OptimizerFactory<M, 1>
+        + OptimizerFactory<Y, HH>
+        + OptimizerFactory<Y, 1>,
+{
+    fn visit_layers(
+        &mut self,
+        f: &mut dyn FnMut(&str, &mut dyn Parameters),
+    ) {
+        f("embed", &mut self.embed);
+        f("lstm", &mut self.lstm);
+        f("out", &mut self.out);
+    }
+}
+
+fn mat_to_array<const N: usize, const X: usize>(
+    m: SMatrix<f32, N, X>,
+) -> [SVector<f32, X>; N] {
+    let mut out = [SVector::zeros(); N];
+    m.row_iter().enumerate().for_each(|(i, row)| {
+        out[i] = row.transpose();
+    });
+    out
+}
@@ -2,22 +2,42 @@ use std::sync::mpsc::Sender;
 
 use nalgebra::SVector;
 
+use crate::layers::gather_params;
+use crate::layers::scatter_params;
+use crate::layers::Layers;
+use crate::layers::Parameters;
+use crate::loss::seqcrossent::SeqCrossEntropy;
+use crate::optimizers::clip::GradClip;
+use crate::optimizers::ema::Ema;
+use crate::optimizers::groups::update_params_grouped;
+use crate::optimizers::groups::ParamGroup;
+use crate::optimizers::lbfgs::Lbfgs;
+use crate::optimizers::sam::Sam;
+
 pub mod ann;
 pub mod ann4;
+pub mod annparam;
+pub mod beam;
+pub mod charlm;
 pub mod cnn;
 pub mod cnn2;
 pub mod cnn3;
+pub mod grusent;
 pub mod lstmsent;
+pub mod lstmtagger;
 pub mod rnnsent;
+pub mod sampling;
+pub mod seq2seq;
 pub mod transformer1;
 
-pub trait NeuralNetwork<const Y: usize> {
+pub trait NeuralNetwork<const Y: usize>: Layers {
     type ModelInput;
     fn new() -> Self;
     fn feedforward(
         &mut self,
         x: Self::ModelInput,
     ) -> SVector<f32, Y>;
+    // computes the gradients, update_params applies them
     fn backprop(
         &mut self,
         y_out: SVector<f32, Y>,
@@ -32,6 +52,10 @@ pub trait NeuralNetwork<const Y: usize> {
 pub struct NNClassifierModel<T, const Y: usize> {
     model: T,
     debug_channel: Option<Sender<f32>>,
+    grad_clip: Option<GradClip>,
+    param_groups: Vec<ParamGroup>,
+    ema: Option<Ema>,
+    sam: Option<Sam>,
 }
 
 impl<T, const Y: usize> NNClassifierModel<T, Y>
@@ -42,12 +66,70 @@ where
 {
     pub fn new(debug_channel: Option<Sender<f32>>) -> Self {
         let model = T::new();
+        let grad_clip = None;
+        let param_groups = Vec::new();
+        let ema = None;
+        let sam = None;
         Self {
             model,
             debug_channel,
+            grad_clip,
+            param_groups,
+            ema,
+            sam,
         }
     }
 
+    pub fn with_grad_clip(
+        mut self,
+        clip: GradClip,
+    ) -> Self {
+        self.grad_clip = Some(clip);
+        self
+    }
+
+    // keeps an exponential moving average of the weights during
+    // training, which predict and validate then use
+    pub fn with_ema(mut self, decay: f32) -> Self {
+        self.ema = Some(Ema::new(decay));
+        self
+    }
+
+    // trains with sharpness-aware minimization, which costs a
+    // second feedforward and backprop per sample
+    pub fn with_sam(mut self, rho: f32) -> Self {
+        self.sam = Some(Sam::new(rho));
+        self
+    }
+
+    // layers named by the group get its learning rate scale and
+    // weight decay, earlier groups take precedence
+    pub fn with_param_group(
+        mut self,
+        group: ParamGroup,
+    ) -> Self {
+        self.param_groups.push(group);
+        self
+    }
+
+    // stops updating the named layers, e.g. freeze(&["conv*"])
+    // to only fine-tune the head of MyCnn
+    pub fn freeze(&mut self, layers: &[&str]) {
+        let group = ParamGroup::new(layers).frozen();
+        self.param_groups.insert(0, group);
+    }
+
+    pub fn unfreeze(&mut self, layers: &[&str]) {
+        self.param_groups.iter_mut().for_each(|g| {
+            if g.frozen {
+                g.layers
+                    .retain(|l| !layers.contains(&&l[..]));
+            }
+        });
+        self.param_groups
+            .retain(|g| !(g.frozen && g.layers.is_empty()));
+    }
+
     pub fn train(
         &mut self,
         x_train: &[T::ModelInput],
@@ -60,6 +142,7 @@ where
             );
         }
         // begin training
+        self.model.set_training(true);
         let n = x_train.len();
         const M: usize = 400;
         let k = n / M;
@@ -90,10 +173,91 @@ where
                 }
             }
             self.model.backprop(y_out, y);
+            if let Some(sam) = self.sam.as_mut() {
+                sam.perturb(&mut self.model);
+                let y_out = self.model.feedforward(x);
+                self.model.backprop(y_out, y);
+                sam.restore(&mut self.model);
+            }
+            if let Some(clip) = self.grad_clip {
+                clip.apply(&mut self.model);
+            }
+            update_params_grouped(
+                &mut self.model,
+                &self.param_groups,
+            );
+            if let Some(ema) = self.ema.as_mut() {
+                ema.update(&mut self.model);
+            }
+        }
+        self.model.set_training(false);
+    }
+
+    // lets the optimizers finalize the weights (SWA loads its
+    // average), to call once after the last call to train
+    pub fn finish(&mut self) {
+        self.model.finish_params();
+    }
+
+    // full batch training on the mean loss, the optimizers of
+    // the layers are not used
+    pub fn train_lbfgs(
+        &mut self,
+        x_train: &[T::ModelInput],
+        y_train: &[SVector<f32, Y>],
+        lbfgs: &mut Lbfgs,
+        max_iter: usize,
+    ) {
+        if x_train.len() != y_train.len() {
+            panic!(
+                "x_train and y_train have different sizes \
+                 of samples"
+            );
         }
+        let n = x_train.len();
+        let model = &mut self.model;
+        let mut w = gather_params(model);
+        let mut f = |w: &[f32]| {
+            scatter_params(model, w);
+            let mut loss = 0.;
+            let mut grad = vec![0.; w.len()];
+            for i in 0..n {
+                let y_out = model.feedforward(x_train[i]);
+                loss += T::loss(&y_out, &y_train[i]);
+                model.backprop(y_out, y_train[i]);
+                let mut k = 0;
+                model.visit_params(&mut |_, g| {
+                    g.iter().for_each(|gi| {
+                        grad[k] += gi / n as f32;
+                        k += 1;
+                    });
+                });
+            }
+            (loss / n as f32, grad)
+        };
+        for i in 0..max_iter {
+            let cost = lbfgs.step(&mut w, &mut f);
+            if let Some(channel) =
+                self.debug_channel.as_ref()
+            {
+                print!(
+                    "Training completion: \r{:.0}%",
+                    (i as f32 / max_iter as f32) * 100.
+                );
+                channel.send(cost).unwrap();
+            }
+            if lbfgs.converged() {
+                break;
+            }
+        }
+        scatter_params(&mut self.model, &w);
     }
 
     pub fn predict(&mut self, x: T::ModelInput) -> usize {
+        self.with_ema_weights(|s| s.classify(x))
+    }
+
+    fn classify(&mut self, x: T::ModelInput) -> usize {
         let y = self.model.feedforward(x);
         y.into_iter()
             .enumerate()
@@ -121,17 +285,219 @@ where
         }
 
         let n = x_test.len();
-        let count: f32 = (0..n)
-            .map(|i| {
-                if self.predict(x_test[i].clone())
-                    == y_test[i]
-                {
-                    1.
-                } else {
-                    0.
-                }
-            })
-            .sum();
+        let count: f32 = self.with_ema_weights(|s| {
+            (0..n)
+                .map(|i| {
+                    if s.classify(x_test[i].clone())
+                        == y_test[i]
+                    {
+                        1.
+                    } else {
+                        0.
+                    }
+                })
+                .sum()
+        });
         count / n as f32
     }
+
+    // runs f with the averaged weights loaded if there is an ema
+    fn with_ema_weights<R>(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> R,
+    ) -> R {
+        let Some(mut ema) = self.ema.take() else {
+            return f(self);
+        };
+        ema.swap(&mut self.model);
+        let out = f(self);
+        ema.swap(&mut self.model);
+        self.ema = Some(ema);
+        out
+    }
+}
+
+// model giving one of Y tags (pos, ner...) to every token of a
+// sequence of at most N tokens, given as ids below vocab. its
+// embeddings are trained with the rest of the model
+pub trait SequenceTagger<const N: usize, const Y: usize>:
+    Layers
+{
+    fn new(vocab: usize) -> Self;
+    // logits of the tags of the first N tokens
+    fn feedforward(
+        &mut self,
+        tokens: &[usize],
+    ) -> [SVector<f32, Y>; N];
+    // computes the gradients from the ones of the logits,
+    // update_params applies them
+    fn backprop(&mut self, g: [SVector<f32, Y>; N]);
+}
+
+// trains a SequenceTagger with softmax and cross entropy at every
+// token, the padding of the sequences is ignored
+pub struct NNTaggerModel<T, const N: usize, const Y: usize>
+{
+    model: T,
+    debug_channel: Option<Sender<f32>>,
+    grad_clip: Option<GradClip>,
+}
+
+impl<T, const N: usize, const Y: usize>
+    NNTaggerModel<T, N, Y>
+where
+    T: SequenceTagger<N, Y>,
+{
+    pub fn new(
+        vocab: usize,
+        debug_channel: Option<Sender<f32>>,
+    ) -> Self {
+        let model = T::new(vocab);
+        let grad_clip = None;
+        Self {
+            model,
+            debug_channel,
+            grad_clip,
+        }
+    }
+
+    pub fn with_grad_clip(
+        mut self,
+        clip: GradClip,
+    ) -> Self {
+        self.grad_clip = Some(clip);
+        self
+    }
+
+    // y_train holds the tag of every token, anything past the
+    // length of the sequence is ignored
+    pub fn train(
+        &mut self,
+        x_train: &[Vec<usize>],
+        y_train: &[[usize; N]],
+    ) {
+        if x_train.len() != y_train.len() {
+            panic!(
+                "x_train and y_train have different sizes \
+                 of samples"
+            );
+        }
+        self.model.set_training(true);
+        let n = x_train.len();
+        const M: usize = 400;
+        let k = n / M;
+        for i in 0..n {
+            let x = &x_train[i];
+            let len = x.len().min(N);
+            let y = &y_train[i];
+            let logits = self.model.feedforward(x);
+            if let Some(channel) =
+                self.debug_channel.as_ref()
+            {
+                if n < M || i % k == 0 {
+                    print!(
+                        "Training completion: \r{:.0}%",
+                        (i as f32 / n as f32) * 100.
+                    );
+                    let cost = SeqCrossEntropy::func(
+                        &logits, y, len,
+                    );
+                    channel.send(cost).unwrap();
+                }
+            }
+            let g = SeqCrossEntropy::grad(&logits, y, len);
+            self.model.backprop(g);
+            if let Some(clip) = self.grad_clip {
+                clip.apply(&mut self.model);
+            }
+            self.model.update_params();
+        }
+        self.model.set_training(false);
+    }
+
+    // lets the optimizers finalize the weights, to call once
+    // after the last call to train
+    pub fn finish(&mut self) {
+        self.model.finish_params();
+    }
+
+    // most likely tag of each token
+    pub fn predict(&mut self, x: &[usize]) -> Vec<usize> {
+        let logits = self.model.feedforward(x);
+        logits[..x.len().min(N)]
+            .iter()
+            .map(|l| l.argmax().0)
+            .collect()
+    }
+
+    // fraction of the tokens tagged right
+    pub fn validate(
+        &mut self,
+        x_test: &[Vec<usize>],
+        y_test: &[[usize; N]],
+    ) -> f32 {
+        if x_test.len() != y_test.len() {
+            panic!(
+                "x_test and y_test have different sizes \
+                 of samples"
+            );
+        }
+        let mut correct = 0;
+        let mut total = 0;
+        for i in 0..x_test.len() {
+            let tags = self.predict(&x_test[i]);
+            correct += tags
+                .iter()
+                .zip(y_test[i].iter())
+                .filter(|(p, y)| p == y)
+                .count();
+            total += tags.len();
+        }
+        correct as f32 / total as f32
+    }
+}
+
+#[test]
+fn test_train_twice_then_finish() {
+    use crate::activation::tanh::Tanh;
+    use crate::loss::mse::Mse;
+    use crate::optimizers::sgd::SgdFactory;
+    use crate::optimizers::swa::SwaFactory;
+
+    type O = SwaFactory<SgdFactory<1, 10>, 2, 3, 1, 2>;
+    type Model = NNClassifierModel<
+        ann::Ann<2, 2, 3, 2, Tanh, Mse, O>,
+        2,
+    >;
+    let x = (0..8)
+        .map(|i| {
+            let i = i as f32;
+            SVector::<f32, 2>::new(i / 8., 1. - i / 4.)
+        })
+        .collect::<Vec<_>>();
+    let y = (0..8)
+        .map(|i| {
+            let mut y = SVector::<f32, 2>::zeros();
+            y[i % 2] = 1.;
+            y
+        })
+        .collect::<Vec<_>>();
+
+    // two epochs in two calls train like one call on both, the
+    // average only replaces the weights in finish
+    crate::rng::reseed(4);
+    let mut twice = Model::new(None);
+    twice.train(&x, &y);
+    twice.train(&x, &y);
+    crate::rng::reseed(4);
+    let mut once = Model::new(None);
+    once.train(
+        &[&x[..], &x[..]].concat(),
+        &[&y[..], &y[..]].concat(),
+    );
+    let w_once = gather_params(&mut once.model);
+    assert_eq!(gather_params(&mut twice.model), w_once);
+
+    once.finish();
+    assert_ne!(gather_params(&mut once.model), w_once);
 }
@@ -4,17 +4,23 @@ use nalgebra::SVector;
 use super::NeuralNetwork;
 use crate::activation::sigmoid::Sigmoid;
 use crate::layers::dense::Dense;
+use crate::layers::masked::Masked;
 use crate::layers::rnncell::RnnCell;
+use crate::layers::stacked::Stacked;
+use crate::layers::Layers;
+use crate::layers::Parameters;
 use crate::loss::crossent::CrossEntropy;
 use crate::loss::LossFunction;
-use crate::optimizers::adam::AdamFactory;
 use crate::optimizers::OptimizerFactory;
 
 const H: usize = 100;
 
 pub const HIDDEN_LAYER_DIM: usize = 10;
 pub const HIDDEN_LAYER_NUM: usize = 1;
+pub const RNN_LAYER_NUM: usize = 2;
+const RNN_DROPOUT: f32 = 0.2;
 // N is number of words, X is dim of word embedding, Y is sentiment dimensions
+// OR optimizes the recurrent cell and O the dense head
 pub struct RnnSentimentAnalyzer<
     const N: usize,
     const X: usize,
@@ -24,13 +30,16 @@ pub struct RnnSentimentAnalyzer<
         + OptimizerFactory<HIDDEN_LAYER_DIM, HIDDEN_LAYER_DIM>
         + OptimizerFactory<Y, HIDDEN_LAYER_DIM>
         + OptimizerFactory<Y, 1>,
+    OR: OptimizerFactory<H, X>
+        + OptimizerFactory<H, H>
+        + OptimizerFactory<H, 1>,
 > {
-    rnn: RnnCell<
+    rnn: Stacked<
         X,
         H,
-        H,
         N,
-        AdamFactory<1, 100, 9, 10, 9, 10>,
+        RnnCell<X, H, H, N, OR>,
+        RnnCell<H, H, H, N, OR>,
     >,
     dense: Dense<
         H,
@@ -40,32 +49,51 @@ pub struct RnnSentimentAnalyzer<
         Sigmoid,
         O,
     >,
+    // tokens in the last input
+    len: usize,
 }
 
-impl<const N: usize, const X: usize, const Y: usize, O>
-    NeuralNetwork<Y> for RnnSentimentAnalyzer<N, X, Y, O>
+impl<
+        const N: usize,
+        const X: usize,
+        const Y: usize,
+        O,
+        OR,
+    > NeuralNetwork<Y>
+    for RnnSentimentAnalyzer<N, X, Y, O, OR>
 where
     O: OptimizerFactory<HIDDEN_LAYER_DIM, H>
         + OptimizerFactory<HIDDEN_LAYER_DIM, 1>
         + OptimizerFactory<HIDDEN_LAYER_DIM, HIDDEN_LAYER_DIM>
         + OptimizerFactory<Y, HIDDEN_LAYER_DIM>
         + OptimizerFactory<Y, 1>,
+    OR: OptimizerFactory<H, X>
+        + OptimizerFactory<H, H>
+        + OptimizerFactory<H, 1>,
 {
-    type ModelInput = SMatrix<f32, N, X>;
+    type ModelInput = Masked<N, X>;
 
     fn new() -> Self {
-        let rnn = RnnCell::new();
+        let rest = (1..RNN_LAYER_NUM)
+            .map(|_| RnnCell::new())
+            .collect();
+        let rnn = Stacked::new(RnnCell::new(), rest)
+            .with_dropout(RNN_DROPOUT)
+            .with_residual();
         let dense = Dense::new();
-        Self { rnn, dense }
+        let len = N;
+        Self { rnn, dense, len }
     }
 
     fn feedforward(
         &mut self,
         x: Self::ModelInput,
     ) -> SVector<f32, Y> {
-        let x = mat_to_array(x);
-        let x = self.rnn.ff(x);
-        let x = x[N - 1];
+        self.len = x.len;
+        let x = mat_to_array(x.x);
+        let x = self.rnn.ff_masked(x, self.len);
+        // state after the last token, not after the padding
+        let x = x[self.len - 1];
         let x = self.dense.ff(x);
         x
     }
@@ -78,7 +106,7 @@ where
         let g = CrossEntropy::grad(y_out, y_test);
         let g = self.dense.bp(g);
         let mut garr = [SMatrix::zeros(); N];
-        garr[N - 1] = g;
+        garr[self.len - 1] = g;
         self.rnn.bp(garr);
     }
 
@@ -90,6 +118,32 @@ where
     }
 }
 
+impl<
+        const N: usize,
+        const X: usize,
+        const Y: usize,
+        O,
+        OR,
+    > Layers for RnnSentimentAnalyzer<N, X, Y, O, OR>
+where
+    O: OptimizerFactory<HIDDEN_LAYER_DIM, H>
+        + OptimizerFactory<HIDDEN_LAYER_DIM, 1>
+        + OptimizerFactory<HIDDEN_LAYER_DIM, HIDDEN_LAYER_DIM>
+        + OptimizerFactory<Y, HIDDEN_LAYER_DIM>
+        + OptimizerFactory<Y, 1>,
+    OR: OptimizerFactory<H, X>
+        + OptimizerFactory<H, H>
+        + OptimizerFactory<H, 1>,
+{
+    fn visit_layers(
+        &mut self,
+        f: &mut dyn FnMut(&str, &mut dyn Parameters),
+    ) {
+        f("rnn", &mut self.rnn);
+        f("dense", &mut self.dense);
+    }
+}
+
 fn mat_to_array<const N: usize, const X: usize>(
     m: SMatrix<f32, N, X>,
 ) -> [SVector<f32, X>; N] {
@@ -0,0 +1,130 @@
+use nalgebra::SVector;
+use rand::Rng;
+
+use crate::layers::softmax::Softmax;
+use crate::rng;
+
+// draws the next token from the logits of a generative model.
+// the logits are divided by the temperature, then only the top_k
+// most likely tokens and the smallest set of them whose
+// probabilities add up to top_p are kept (nucleus sampling)
+#[derive(Clone, Copy, Debug)]
+pub struct Sampler {
+    // 0 always picks the most likely token
+    pub temperature: f32,
+    pub top_k: Option<usize>,
+    pub top_p: Option<f32>,
+}
+
+impl Sampler {
+    pub fn new() -> Self {
+        Self {
+            temperature: 1.,
+            top_k: None,
+            top_p: None,
+        }
+    }
+
+    pub fn temperature(mut self, temperature: f32) -> Self {
+        assert!(temperature >= 0.);
+        self.temperature = temperature;
+        self
+    }
+
+    pub fn top_k(mut self, top_k: usize) -> Self {
+        assert!(top_k > 0);
+        self.top_k = Some(top_k);
+        self
+    }
+
+    pub fn top_p(mut self, top_p: f32) -> Self {
+        assert!(top_p > 0. && top_p <= 1.);
+        self.top_p = Some(top_p);
+        self
+    }
+
+    pub fn sample<const V: usize>(
+        &self,
+        logits: &SVector<f32, V>,
+    ) -> usize {
+        if self.temperature == 0. {
+            return logits.argmax().0;
+        }
+        let z = logits / self.temperature;
+        let p = Softmax::new().ff(z.add_scalar(-z.max()));
+
+        // most likely first
+        let mut candidates = (0..V).collect::<Vec<_>>();
+        candidates.sort_by(|&i, &j| p[j].total_cmp(&p[i]));
+        if let Some(k) = self.top_k {
+            candidates.truncate(k);
+        }
+        if let Some(top_p) = self.top_p {
+            let mut sum = 0.;
+            let keep = candidates
+                .iter()
+                .take_while(|&&i| {
+                    let below = sum < top_p;
+                    sum += p[i];
+                    below
+                })
+                .count();
+            candidates.truncate(keep.max(1));
+        }
+
+        let total =
+            candidates.iter().map(|&i| p[i]).sum::<f32>();
+        let mut r =
+            rng::with_rng(|rng| rng.gen_range(0. ..total));
+        for &i in &candidates {
+            if r < p[i] {
+                return i;
+            }
+            r -= p[i];
+        }
+        // rounding left r slightly above the last probability
+        candidates[candidates.len() - 1]
+    }
+}
+
+impl Default for Sampler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[test]
+fn test_sampler() {
+    let logits = SVector::<f32, 4>::new(1., 3., 2., 0.);
+    assert_eq!(
+        Sampler::new().temperature(0.).sample(&logits),
+        1
+    );
+    assert_eq!(Sampler::new().top_k(1).sample(&logits), 1);
+    // the best token alone has about 0.64 of the mass
+    assert_eq!(
+        Sampler::new().top_p(0.5).sample(&logits),
+        1
+    );
+
+    crate::rng::reseed(3);
+    let mut counts = [0; 4];
+    for _ in 0..1000 {
+        counts[Sampler::new().top_k(2).sample(&logits)] +=
+            1;
+    }
+    assert_eq!(counts[0] + counts[3], 0);
+    // renormalized over the two kept tokens: e / (1 + e)
+    assert!(
+        (counts[1] as f32 / 1000. - 0.731).abs() < 0.05
+    );
+
+    let mut counts = [0; 4];
+    for _ in 0..1000 {
+        counts
+            [Sampler::new().top_p(0.9).sample(&logits)] +=
+            1;
+    }
+    assert_eq!(counts[3], 0);
+    assert!(counts[0] > 0 && counts[2] > 0);
+}
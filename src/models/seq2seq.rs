@@ -0,0 +1,259 @@
+use nalgebra::SVector;
+
+use super::beam::BeamSearch;
+use crate::activation::noact::NoActivation;
+use crate::activation::tanh::Tanh;
+use crate::layers::crossattention::CrossAttention;
+use crate::layers::crossattention::Score;
+use crate::layers::lstm::Lstm;
+use crate::layers::timedense::TimeDense;
+use crate::layers::Layers;
+use crate::layers::Parameters;
+use crate::loss::seqcrossent::SeqCrossEntropy;
+use crate::optimizers::OptimizerFactory;
+
+// tokens are the digits, BOS and EOS
+pub const V: usize = 12;
+pub const BOS: usize = 10;
+pub const EOS: usize = 11;
+// longest source and target, the target ends with EOS
+pub const S: usize = 10;
+pub const T: usize = S + 1;
+pub const H: usize = 64;
+const HV: usize = H + V;
+const HH: usize = 2 * H;
+
+// lstm encoder and decoder, with the decoder states attending over
+// the encoder states (luong style, after the decoder). the
+// decoder starts from the final state of the encoder
+pub struct Seq2Seq<
+    A: Score<H, H>,
+    O: OptimizerFactory<H, HV>
+        + OptimizerFactory<H, 1>
+        + OptimizerFactory<H, HH>
+        + OptimizerFactory<V, H>
+        + OptimizerFactory<V, 1>,
+> {
+    encoder: Lstm<V, H, S, HV, O>,
+    decoder: Lstm<V, H, T, HV, O>,
+    attention: CrossAttention<H, H, S, T, A>,
+    // tanh(Wc [c_t, d_t]) and then the logits of the next token
+    combine: TimeDense<HH, H, T, Tanh, O>,
+    out: TimeDense<H, V, T, NoActivation, O>,
+}
+
+impl<A, O> Seq2Seq<A, O>
+where
+    A: Score<H, H>,
+    O: OptimizerFactory<H, HV>
+        + OptimizerFactory<H, 1>
+        + OptimizerFactory<H, HH>
+        + OptimizerFactory<V, H>
+        + OptimizerFactory<V, 1>,
+{
+    pub fn new() -> Self {
+        let encoder = Lstm::new();
+        let decoder = Lstm::new();
+        let attention = CrossAttention::new();
+        let combine = TimeDense::new();
+        let out = TimeDense::new();
+        Self {
+            encoder,
+            decoder,
+            attention,
+            combine,
+            out,
+        }
+    }
+
+    // logits of the token after each of tgt_in
+    pub fn feedforward(
+        &mut self,
+        src: &[usize],
+        tgt_in: &[usize],
+    ) -> [SVector<f32, V>; T] {
+        let enc =
+            self.encoder.ff_masked(one_hot(src), src.len());
+        let (h, c) = self.encoder.final_state();
+        self.decoder.set_initial_state(h, c);
+        let dec = self
+            .decoder
+            .ff_masked(one_hot(tgt_in), tgt_in.len());
+        let c = self.attention.ff(dec, enc, src.len());
+        let mut cd = [SVector::<f32, HH>::zeros(); T];
+        for t in 0..T {
+            cd[t].fixed_rows_mut::<H>(0).copy_from(&c[t]);
+            cd[t].fixed_rows_mut::<H>(H).copy_from(&dec[t]);
+        }
+        let x = self.combine.ff(cd);
+        self.out.ff(x)
+    }
+
+    // computes the gradients, update_params applies them
+    pub fn backprop(&mut self, g: [SVector<f32, V>; T]) {
+        let g = self.out.bp(g);
+        let gcd = self.combine.bp(g);
+        let mut gc = [SVector::zeros(); T];
+        let mut gdec = [SVector::zeros(); T];
+        for t in 0..T {
+            gc[t] = gcd[t].fixed_rows::<H>(0).into();
+            gdec[t] = gcd[t].fixed_rows::<H>(H).into();
+        }
+        let (gd, genc) = self.attention.bp(gc);
+        for t in 0..T {
+            gdec[t] += gd[t];
+        }
+        self.decoder.bp(gdec);
+        let (gh, gc) = self.decoder.initial_state_grad();
+        self.encoder.bp_with_state(genc, gh, gc);
+    }
+
+    // teacher forcing: the decoder reads the right previous
+    // token whatever it predicted, returns the loss
+    pub fn train_step(
+        &mut self,
+        src: &[usize],
+        tgt: &[usize],
+    ) -> f32 {
+        assert!(!src.is_empty() && src.len() <= S);
+        assert!(tgt.len() < T);
+        let tgt_in = [&[BOS], tgt].concat();
+        let mut tags = [0; T];
+        tags[..tgt.len()].copy_from_slice(tgt);
+        tags[tgt.len()] = EOS;
+        let len = tgt.len() + 1;
+        let logits = self.feedforward(src, &tgt_in);
+        let loss =
+            SeqCrossEntropy::func(&logits, &tags, len);
+        self.backprop(SeqCrossEntropy::grad(
+            &logits, &tags, len,
+        ));
+        loss
+    }
+
+    // greedy decoding, feeds back the most likely token until
+    // EOS. the decoder is rerun over the whole prefix at every
+    // step
+    pub fn translate(
+        &mut self,
+        src: &[usize],
+    ) -> Vec<usize> {
+        assert!(!src.is_empty() && src.len() <= S);
+        let mut tgt_in = vec![BOS];
+        for t in 0..T {
+            let logits = self.feedforward(src, &tgt_in);
+            let next = logits[t].argmax().0;
+            if next == EOS {
+                break;
+            }
+            tgt_in.push(next);
+        }
+        tgt_in.split_off(1)
+    }
+
+    // the best output of a beam search, which keeps several
+    // prefixes instead of only the most likely one
+    pub fn translate_beam(
+        &mut self,
+        src: &[usize],
+        beam: &BeamSearch,
+    ) -> Vec<usize> {
+        assert!(!src.is_empty() && src.len() <= S);
+        assert!(beam.max_len <= T && beam.eos == Some(EOS));
+        let mut step = |prefix: &[usize]| {
+            self.feedforward(src, prefix)[prefix.len() - 1]
+        };
+        beam.search(&mut step, &[BOS]).swap_remove(0).tokens
+    }
+}
+
+impl<A, O> Default for Seq2Seq<A, O>
+where
+    A: Score<H, H>,
+    O: OptimizerFactory<H, HV>
+        + OptimizerFactory<H, 1>
+        + OptimizerFactory<H, HH>
+        + OptimizerFactory<V, H>
+        + OptimizerFactory<V, 1>,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<A, O> Layers for Seq2Seq<A, O>
+where
+    A: Score<H, H>,
+    O: OptimizerFactory<H, HV>
+        + OptimizerFactory<H, 1>
+        + OptimizerFactory<H, HH>
+        + OptimizerFactory<V, H>
+        + OptimizerFactory<V, 1>,
+{
+    fn visit_layers(
+        &mut self,
+        f: &mut dyn FnMut(&str, &mut dyn Parameters),
+    ) {
+        f("encoder", &mut self.encoder);
+        f("decoder", &mut self.decoder);
+        f("attention", &mut self.attention);
+        f("combine", &mut self.combine);
+        f("out", &mut self.out);
+    }
+}
+
+fn one_hot<const N: usize>(
+    tokens: &[usize],
+) -> [SVector<f32, V>; N] {
+    let mut out = [SVector::zeros(); N];
+    for (t, &token) in tokens.iter().take(N).enumerate() {
+        out[t][token] = 1.;
+    }
+    out
+}
+
+#[test]
+fn test_seq2seq_reversal() {
+    use crate::layers::crossattention::Multiplicative;
+    use crate::optimizers::adam::AdamFactory;
+
+    type O = AdamFactory<1, 100, 9, 10, 999, 1000>;
+
+    // the model lives on the stack
+    std::thread::Builder::new()
+        .stack_size(256 * 1024 * 1024)
+        .spawn(|| {
+            crate::rng::reseed(2);
+            let mut model =
+                Seq2Seq::<Multiplicative<H, H, O>, O>::new(
+                );
+            let data = [
+                (vec![1, 2, 3], vec![3, 2, 1]),
+                (vec![4, 5], vec![5, 4]),
+            ];
+            let mut losses = Vec::new();
+            for _ in 0..40 {
+                let mut loss = 0.;
+                for (src, tgt) in &data {
+                    loss += model.train_step(src, tgt);
+                    model.update_params();
+                }
+                losses.push(loss);
+            }
+            // teacher forcing brings the loss down
+            assert!(
+                losses[losses.len() - 1] < 0.1 * losses[0]
+            );
+            let beam = BeamSearch::new(3, T).eos(EOS);
+            for (src, tgt) in &data {
+                assert_eq!(model.translate(src), *tgt);
+                assert_eq!(
+                    model.translate_beam(src, &beam),
+                    *tgt
+                );
+            }
+        })
+        .unwrap()
+        .join()
+        .unwrap();
+}
@@ -5,8 +5,12 @@ use super::NeuralNetwork;
 use crate::activation::relu::Relu;
 use crate::activation::sigmoid::Sigmoid;
 use crate::layers::attention::Attention;
+use crate::layers::masked::mask_rows;
+use crate::layers::masked::Masked;
 use crate::layers::posencoder::PosEncoder;
 use crate::layers::sequential::Sequential;
+use crate::layers::Layers;
+use crate::layers::Parameters;
 use crate::loss::crossent::CrossEntropy;
 use crate::loss::LossFunction;
 use crate::optimizers::OptimizerFactory;
@@ -42,6 +46,9 @@ pub struct Transformer1<
     seqf2: Sequential<L1, L2, Sigmoid, O>,
     seqf3: Sequential<L2, L3, Sigmoid, O>,
     seqf4: Sequential<L3, Y, Sigmoid, O>,
+    // tokens in the last input, the rows after them are kept at
+    // zero between the blocks
+    len: usize,
 }
 
 impl<O> NeuralNetwork<Y> for Transformer1<O>
@@ -59,7 +66,7 @@ where
         + OptimizerFactory<Y, L3>
         + OptimizerFactory<Y, 1>,
 {
-    type ModelInput = SMatrix<f32, N, M>;
+    type ModelInput = Masked<N, M>;
 
     fn new() -> Self {
         let posencoder = PosEncoder::new();
@@ -82,6 +89,7 @@ where
         let seqf2 = Sequential::new();
         let seqf3 = Sequential::new();
         let seqf4 = Sequential::new();
+        let len = N;
 
         Self {
             posencoder,
@@ -91,6 +99,7 @@ where
             seqf2,
             seqf3,
             seqf4,
+            len,
         }
     }
 
@@ -98,12 +107,15 @@ where
         &mut self,
         x: Self::ModelInput,
     ) -> SVector<f32, Y> {
-        let mut x = self.posencoder.ff(x);
+        self.len = x.len;
+        let mut x =
+            mask_rows(self.posencoder.ff(x.x), self.len);
         for t in 0..T {
-            x = self.attention[t].ff(x);
+            x = self.attention[t].ff_masked(x, self.len);
+            x = mask_rows(x, self.len);
             let x_tmp = flatten(x);
             let x_tmp = self.seq[t].ff(x_tmp);
-            x = unflatten(x_tmp);
+            x = mask_rows(unflatten(x_tmp), self.len);
         }
         let x = flatten(x);
         let x = self.seqf1.ff(x);
@@ -125,9 +137,10 @@ where
         let g = self.seqf1.bp(g);
         let mut g = unflatten(g);
         for t in (0..T).rev() {
-            let g_tmp = flatten(g);
+            let g_tmp = flatten(mask_rows(g, self.len));
             let g_tmp = self.seq[t].bp(g_tmp);
-            let g_tmp = unflatten(g_tmp);
+            let g_tmp =
+                mask_rows(unflatten(g_tmp), self.len);
             g = self.attention[t].bp(g_tmp);
         }
     }
@@ -140,6 +153,39 @@ where
     }
 }
 
+impl<O> Layers for Transformer1<O>
+where
+    O: OptimizerFactory<M, D>
+        + OptimizerFactory<M, M>
+        + OptimizerFactory<NM, NM>
+        + OptimizerFactory<NM, 1>
+        + OptimizerFactory<L1, NM>
+        + OptimizerFactory<L1, 1>
+        + OptimizerFactory<L2, L1>
+        + OptimizerFactory<L2, 1>
+        + OptimizerFactory<L3, L2>
+        + OptimizerFactory<L3, 1>
+        + OptimizerFactory<Y, L3>
+        + OptimizerFactory<Y, 1>,
+{
+    fn visit_layers(
+        &mut self,
+        f: &mut dyn FnMut(&str, &mut dyn Parameters),
+    ) {
+        for t in 0..T {
+            f(
+                &format!("attention{t}"),
+                &mut self.attention[t],
+            );
+            f(&format!("seq{t}"), &mut self.seq[t]);
+        }
+        f("seqf1", &mut self.seqf1);
+        f("seqf2", &mut self.seqf2);
+        f("seqf3", &mut self.seqf3);
+        f("seqf4", &mut self.seqf4);
+    }
+}
+
 fn flatten(v: SMatrix<f32, N, M>) -> SVector<f32, NM> {
     let mut out = SVector::zeros();
     for i in 0..N {
@@ -0,0 +1,79 @@
+use nalgebra::SMatrix;
+
+use super::component_invsqrt;
+use super::Optimizer;
+use super::OptimizerFactory;
+
+// no learning rate, the gradient is rescaled by the ratio
+// of the running rms of past steps and of past gradients
+pub struct AdaDelta<
+    const RHO_NUM: usize,
+    const RHO_DEN: usize,
+    const R: usize,
+    const C: usize,
+> {
+    g: SMatrix<f32, R, C>,
+    dx: SMatrix<f32, R, C>,
+}
+
+impl<
+        const RHO_NUM: usize,
+        const RHO_DEN: usize,
+        const R: usize,
+        const C: usize,
+    > Optimizer<R, C> for AdaDelta<RHO_NUM, RHO_DEN, R, C>
+{
+    fn init() -> Self {
+        let g = SMatrix::zeros();
+        let dx = SMatrix::zeros();
+        Self { g, dx }
+    }
+
+    fn update_param(
+        &mut self,
+        weight: &mut SMatrix<f32, R, C>,
+        gradient: &SMatrix<f32, R, C>,
+    ) {
+        let rho = RHO_NUM as f32 / RHO_DEN as f32;
+        self.g = rho * self.g
+            + (1. - rho) * gradient.component_mul(gradient);
+        let dx = component_invsqrt(&self.g)
+            .component_div(&component_invsqrt(&self.dx))
+            .component_mul(gradient);
+        self.dx = rho * self.dx
+            + (1. - rho) * dx.component_mul(&dx);
+        *weight -= dx;
+    }
+
+    fn name() -> String {
+        "adadelta".to_string()
+    }
+}
+
+pub struct AdaDeltaFactory<
+    const RHO_NUM: usize,
+    const RHO_DEN: usize,
+>;
+
+impl<
+        const R: usize,
+        const C: usize,
+        const RHO_NUM: usize,
+        const RHO_DEN: usize,
+    > OptimizerFactory<R, C>
+    for AdaDeltaFactory<RHO_NUM, RHO_DEN>
+{
+    type Optimizer = AdaDelta<RHO_NUM, RHO_DEN, R, C>;
+}
+
+#[test]
+fn test_quadratic_trajectory() {
+    super::assert_quadratic_trajectory::<
+        AdaDelta<9, 10, 2, 1>,
+    >(&[
+        [0.996838, -0.496838],
+        [0.993598, -0.493603],
+        [0.990309, -0.490326],
+        [0.986984, -0.48702],
+    ]);
+}
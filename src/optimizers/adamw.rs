@@ -0,0 +1,152 @@
+use nalgebra::SMatrix;
+
+use super::adam::Adam;
+use super::Optimizer;
+use super::OptimizerFactory;
+
+// adam with decoupled weight decay: w -= alpha * (adam step + wd * w)
+pub struct AdamW<
+    const ALPHA_NUM: usize,
+    const ALPHA_DEN: usize,
+    const BETA1_NUM: usize,
+    const BETA1_DEN: usize,
+    const BETA2_NUM: usize,
+    const BETA2_DEN: usize,
+    const WD_NUM: usize,
+    const WD_DEN: usize,
+    const R: usize,
+    const C: usize,
+> {
+    adam: Adam<
+        ALPHA_NUM,
+        ALPHA_DEN,
+        BETA1_NUM,
+        BETA1_DEN,
+        BETA2_NUM,
+        BETA2_DEN,
+        R,
+        C,
+    >,
+    decay: bool,
+}
+
+impl<
+        const ALPHA_NUM: usize,
+        const ALPHA_DEN: usize,
+        const BETA1_NUM: usize,
+        const BETA1_DEN: usize,
+        const BETA2_NUM: usize,
+        const BETA2_DEN: usize,
+        const WD_NUM: usize,
+        const WD_DEN: usize,
+        const R: usize,
+        const C: usize,
+    > Optimizer<R, C>
+    for AdamW<
+        ALPHA_NUM,
+        ALPHA_DEN,
+        BETA1_NUM,
+        BETA1_DEN,
+        BETA2_NUM,
+        BETA2_DEN,
+        WD_NUM,
+        WD_DEN,
+        R,
+        C,
+    >
+{
+    fn init() -> Self {
+        let adam = Adam::init();
+        let decay = true;
+        Self { adam, decay }
+    }
+
+    fn init_bias() -> Self {
+        let adam = Adam::init();
+        let decay = false;
+        Self { adam, decay }
+    }
+
+    fn update_param(
+        &mut self,
+        weight: &mut SMatrix<f32, R, C>,
+        gradient: &SMatrix<f32, R, C>,
+    ) {
+        let old = *weight;
+        self.adam.update_param(weight, gradient);
+        if self.decay {
+            let alpha = ALPHA_NUM as f32 / ALPHA_DEN as f32;
+            let wd = WD_NUM as f32 / WD_DEN as f32;
+            *weight -= alpha * wd * old;
+        }
+    }
+
+    fn name() -> String {
+        "adamw".to_string()
+    }
+}
+
+pub struct AdamWFactory<
+    const ALPHA_NUM: usize,
+    const ALPHA_DEN: usize,
+    const BETA1_NUM: usize,
+    const BETA1_DEN: usize,
+    const BETA2_NUM: usize,
+    const BETA2_DEN: usize,
+    const WD_NUM: usize,
+    const WD_DEN: usize,
+>;
+
+impl<
+        const R: usize,
+        const C: usize,
+        const ALPHA_NUM: usize,
+        const ALPHA_DEN: usize,
+        const BETA1_NUM: usize,
+        const BETA1_DEN: usize,
+        const BETA2_NUM: usize,
+        const BETA2_DEN: usize,
+        const WD_NUM: usize,
+        const WD_DEN: usize,
+    > OptimizerFactory<R, C>
+    for AdamWFactory<
+        ALPHA_NUM,
+        ALPHA_DEN,
+        BETA1_NUM,
+        BETA1_DEN,
+        BETA2_NUM,
+        BETA2_DEN,
+        WD_NUM,
+        WD_DEN,
+    >
+{
+    type Optimizer = AdamW<
+        ALPHA_NUM,
+        ALPHA_DEN,
+        BETA1_NUM,
+        BETA1_DEN,
+        BETA2_NUM,
+        BETA2_DEN,
+        WD_NUM,
+        WD_DEN,
+        R,
+        C,
+    >;
+}
+
+#[test]
+fn test_adamw() {
+    type O = AdamW<1, 10, 9, 10, 999, 1000, 1, 2, 2, 1>;
+    super::assert_quadratic_trajectory::<O>(&[
+        [0.85, -0.375],
+        [0.708249, -0.257993],
+        [0.574974, -0.150503],
+        [0.450515, -0.05471],
+    ]);
+    // biases take plain adam steps
+    super::assert_bias_trajectory::<O>(&[
+        [0.9, -0.4],
+        [0.800412, -0.301187],
+        [0.701586, -0.204871],
+    ]);
+}
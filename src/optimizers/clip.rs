@@ -0,0 +1,90 @@
+use crate::layers::Parameters;
+
+// applied to the gradients of a whole model after backprop and
+// before the optimizers step
+#[derive(Clone, Copy, Debug)]
+pub enum GradClip {
+    // every gradient component clamped to [-v, v]
+    Value(f32),
+    // every gradient rescaled so that the l2 norm of all the
+    // gradients together is at most v
+    GlobalNorm(f32),
+}
+
+impl GradClip {
+    pub fn apply<P: Parameters + ?Sized>(
+        self,
+        params: &mut P,
+    ) {
+        match self {
+            GradClip::Value(v) => clip_by_value(params, v),
+            GradClip::GlobalNorm(v) => {
+                clip_by_global_norm(params, v);
+            }
+        }
+    }
+}
+
+pub fn clip_by_value<P: Parameters + ?Sized>(
+    params: &mut P,
+    v: f32,
+) {
+    params.visit_params(&mut |_, g| {
+        g.iter_mut().for_each(|gi| *gi = gi.clamp(-v, v));
+    });
+}
+
+pub fn global_norm<P: Parameters + ?Sized>(
+    params: &mut P,
+) -> f32 {
+    let mut sum = 0.;
+    params.visit_params(&mut |_, g| {
+        sum += g.iter().map(|gi| gi * gi).sum::<f32>();
+    });
+    sum.sqrt()
+}
+
+// returns the norm before clipping
+pub fn clip_by_global_norm<P: Parameters + ?Sized>(
+    params: &mut P,
+    max_norm: f32,
+) -> f32 {
+    let norm = global_norm(params);
+    if norm > max_norm {
+        let scale = max_norm / norm;
+        params.visit_params(&mut |_, g| {
+            g.iter_mut().for_each(|gi| *gi *= scale);
+        });
+    }
+    norm
+}
+
+#[test]
+fn test_clip_by_global_norm() {
+    use nalgebra::SVector;
+
+    use crate::activation::noact::NoActivation;
+    use crate::layers::sequential::Sequential;
+    use crate::optimizers::sgd::SgdFactory;
+
+    let mut layer = Sequential::<
+        3,
+        2,
+        NoActivation,
+        SgdFactory<1, 1>,
+    >::new();
+    layer.ff(SVector::<f32, 3>::new(1., 2., 3.));
+    layer.bp(SVector::<f32, 2>::new(4., -5.));
+
+    let norm = global_norm(&mut layer);
+    // |g| * |(a, 1)| = sqrt(41) * sqrt(15)
+    assert!((norm - (41f32 * 15.).sqrt()).abs() < 1e-3);
+
+    assert_eq!(clip_by_global_norm(&mut layer, 1.), norm);
+    assert!((global_norm(&mut layer) - 1.).abs() < 1e-5);
+
+    clip_by_value(&mut layer, 0.01);
+    layer.visit_params(&mut |_, g| {
+        assert!(g.iter().all(|gi| gi.abs() <= 0.01));
+    });
+}
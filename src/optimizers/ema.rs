@@ -0,0 +1,93 @@
+use crate::layers::Parameters;
+
+// shadow copy of the parameters of a model, averaged as
+// shadow = decay * shadow + (1 - decay) * w after every step
+pub struct Ema {
+    decay: f32,
+    shadow: Vec<f32>,
+}
+
+impl Ema {
+    pub fn new(decay: f32) -> Self {
+        let shadow = Vec::new();
+        Self { decay, shadow }
+    }
+
+    pub fn update<P: Parameters + ?Sized>(
+        &mut self,
+        params: &mut P,
+    ) {
+        // first call starts the average at the current weights
+        if self.shadow.is_empty() {
+            params.visit_params(&mut |w, _| {
+                self.shadow.extend_from_slice(w)
+            });
+            return;
+        }
+        let decay = self.decay;
+        let mut shadow = self.shadow.iter_mut();
+        params.visit_params(&mut |w, _| {
+            w.iter().zip(&mut shadow).for_each(
+                |(wi, si)| {
+                    *si = decay * *si + (1. - decay) * wi;
+                },
+            );
+        });
+    }
+
+    // exchanges the model weights with the averaged ones, so
+    // calling it twice restores the model
+    pub fn swap<P: Parameters + ?Sized>(
+        &mut self,
+        params: &mut P,
+    ) {
+        let mut shadow = self.shadow.iter_mut();
+        params.visit_params(&mut |w, _| {
+            w.iter_mut().zip(&mut shadow).for_each(
+                |(wi, si)| std::mem::swap(wi, si),
+            );
+        });
+    }
+}
+
+#[test]
+fn test_ema() {
+    use nalgebra::SVector;
+
+    use crate::activation::noact::NoActivation;
+    use crate::layers::sequential::Sequential;
+    use crate::optimizers::sgd::SgdFactory;
+
+    let mut layer = Sequential::<
+        2,
+        1,
+        NoActivation,
+        SgdFactory<1, 1>,
+    >::new();
+    let params = |layer: &mut Sequential<_, _, _, _>| {
+        let mut p = Vec::new();
+        layer.visit_params(&mut |w, _| {
+            p.extend_from_slice(w)
+        });
+        p
+    };
+
+    let mut ema = Ema::new(0.5);
+    ema.update(&mut layer);
+    let w0 = params(&mut layer);
+    let y = layer.ff(SVector::<f32, 2>::new(1., 2.));
+    layer.bp(y);
+    layer.update_params();
+    let w1 = params(&mut layer);
+    ema.update(&mut layer);
+
+    ema.swap(&mut layer);
+    let avg = params(&mut layer);
+    for i in 0..avg.len() {
+        assert!(
+            (avg[i] - 0.5 * (w0[i] + w1[i])).abs() < 1e-6
+        );
+    }
+    ema.swap(&mut layer);
+    assert_eq!(params(&mut layer), w1);
+}
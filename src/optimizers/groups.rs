@@ -0,0 +1,266 @@
+use crate::layers::Layers;
+use crate::layers::Parameters;
+
+// hyperparameters overriding the ones of the optimizers for some
+// of the named layers of a model
+#[derive(Clone, Debug)]
+pub struct ParamGroup {
+    // layer names, a trailing '*' matches every name with that
+    // prefix
+    pub layers: Vec<String>,
+    // multiplies the learning rate of the layer optimizers
+    pub lr_scale: f32,
+    // l2 penalty added to the gradients of the weights of the
+    // group, biases are left alone
+    pub weight_decay: f32,
+    // frozen layers still backpropagate to their inputs but
+    // their parameters are never updated
+    pub frozen: bool,
+}
+
+impl ParamGroup {
+    pub fn new(layers: &[&str]) -> Self {
+        let layers =
+            layers.iter().map(|l| l.to_string()).collect();
+        Self {
+            layers,
+            lr_scale: 1.,
+            weight_decay: 0.,
+            frozen: false,
+        }
+    }
+
+    pub fn lr_scale(mut self, lr_scale: f32) -> Self {
+        self.lr_scale = lr_scale;
+        self
+    }
+
+    pub fn weight_decay(
+        mut self,
+        weight_decay: f32,
+    ) -> Self {
+        self.weight_decay = weight_decay;
+        self
+    }
+
+    pub fn frozen(mut self) -> Self {
+        self.frozen = true;
+        self
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.layers.iter().any(|l| {
+            match l.strip_suffix('*') {
+                Some(prefix) => name.starts_with(prefix),
+                None => l == name,
+            }
+        })
+    }
+
+    pub fn update(&self, layer: &mut dyn Parameters) {
+        if self.frozen {
+            return;
+        }
+        layer.update_params_scaled(
+            self.lr_scale,
+            self.weight_decay,
+        );
+    }
+}
+
+// updates every layer with the first group naming it, or with
+// its own optimizers if there is none
+pub fn update_params_grouped<L: Layers + ?Sized>(
+    model: &mut L,
+    groups: &[ParamGroup],
+) {
+    model.visit_layers(&mut |name, layer| match groups
+        .iter()
+        .find(|g| g.contains(name))
+    {
+        Some(group) => group.update(layer),
+        None => layer.update_params(),
+    });
+}
+
+#[test]
+fn test_param_groups() {
+    use nalgebra::SVector;
+
+    use crate::activation::noact::NoActivation;
+    use crate::layers::sequential::Sequential;
+    use crate::optimizers::sgd::SgdFactory;
+
+    type Layer =
+        Sequential<2, 2, NoActivation, SgdFactory<1, 10>>;
+
+    struct Model {
+        a: Layer,
+        b: Layer,
+    }
+
+    impl Layers for Model {
+        fn visit_layers(
+            &mut self,
+            f: &mut dyn FnMut(&str, &mut dyn Parameters),
+        ) {
+            f("a", &mut self.a);
+            f("b", &mut self.b);
+        }
+    }
+
+    let step = |groups: &[ParamGroup]| {
+        crate::rng::reseed(7);
+        let mut model = Model {
+            a: Layer::new(),
+            b: Layer::new(),
+        };
+        let x = SVector::<f32, 2>::new(1., -1.);
+        let y = model.b.ff(model.a.ff(x));
+        model.a.bp(model.b.bp(y));
+        let mut before = Vec::new();
+        model.visit_params(&mut |w, _| {
+            before.extend_from_slice(w)
+        });
+        update_params_grouped(&mut model, groups);
+        let mut after = Vec::new();
+        model.visit_params(&mut |w, _| {
+            after.extend_from_slice(w)
+        });
+        before
+            .iter()
+            .zip(after.iter())
+            .map(|(b, a)| a - b)
+            .collect::<Vec<f32>>()
+    };
+
+    let plain = step(&[]);
+    let grouped =
+        step(&[ParamGroup::new(&["a"]).lr_scale(0.5)]);
+    // a holds the first 6 parameters (w then b)
+    for i in 0..plain.len() {
+        let scale = if i < 6 { 0.5 } else { 1. };
+        assert!(
+            (grouped[i] - scale * plain[i]).abs() < 1e-6
+        );
+    }
+}
+
+#[test]
+fn test_frozen_group() {
+    use nalgebra::SVector;
+
+    use crate::activation::noact::NoActivation;
+    use crate::layers::sequential::Sequential;
+    use crate::optimizers::sgd::SgdFactory;
+
+    let mut layer = Sequential::<
+        2,
+        2,
+        NoActivation,
+        SgdFactory<1, 10>,
+    >::new();
+    let x = SVector::<f32, 2>::new(1., -1.);
+    let y = layer.ff(x);
+    let dx = layer.bp(y);
+    assert!(dx.norm() > 0.);
+
+    let mut before = Vec::new();
+    layer.visit_params(&mut |w, _| {
+        before.extend_from_slice(w)
+    });
+    ParamGroup::new(&["a"]).frozen().update(&mut layer);
+    let mut after = Vec::new();
+    layer.visit_params(&mut |w, _| {
+        after.extend_from_slice(w)
+    });
+    assert_eq!(before, after);
+}
+
+#[test]
+fn test_group_weight_decay_skips_biases() {
+    use nalgebra::SVector;
+
+    use crate::activation::noact::NoActivation;
+    use crate::layers::sequential::Sequential;
+    use crate::optimizers::sgd::SgdFactory;
+
+    let mut layer = Sequential::<
+        2,
+        2,
+        NoActivation,
+        SgdFactory<1, 10>,
+    >::new();
+    // zero gradients, so only the decay moves the parameters
+    layer.ff(SVector::<f32, 2>::new(1., -1.));
+    layer.bp(SVector::zeros());
+    let mut before = Vec::new();
+    layer.visit_params(&mut |w, _| {
+        before.extend_from_slice(w)
+    });
+    ParamGroup::new(&["a"])
+        .weight_decay(1.)
+        .update(&mut layer);
+    let mut after = Vec::new();
+    layer.visit_params(&mut |w, _| {
+        after.extend_from_slice(w)
+    });
+    // the 4 weights first, then the 2 biases
+    for i in 0..6 {
+        let expected =
+            if i < 4 { 0.9 * before[i] } else { before[i] };
+        assert!((after[i] - expected).abs() < 1e-6);
+    }
+}
+
+#[test]
+fn test_group_lr_scale_with_lookahead() {
+    use nalgebra::SVector;
+
+    use crate::activation::noact::NoActivation;
+    use crate::layers::sequential::Sequential;
+    use crate::optimizers::lookahead::LookaheadFactory;
+    use crate::optimizers::sgd::SgdFactory;
+
+    // the slow weights sync every 2 steps, halfway
+    type Layer<const A: usize> = Sequential<
+        2,
+        2,
+        NoActivation,
+        LookaheadFactory<SgdFactory<1, A>, 2, 1, 2>,
+    >;
+    fn train<P: Parameters>(
+        layer: &mut P,
+        step: impl Fn(&mut P),
+    ) -> Vec<f32> {
+        for _ in 0..4 {
+            step(layer);
+        }
+        let mut p = Vec::new();
+        layer.visit_params(&mut |w, _| {
+            p.extend_from_slice(w)
+        });
+        p
+    }
+    let x = SVector::<f32, 2>::new(1., -1.);
+
+    // a group at half the rate steps like half the learning rate,
+    // the syncs are not scaled
+    crate::rng::reseed(11);
+    let mut scaled = Layer::<5>::new();
+    let scaled = train(&mut scaled, |layer| {
+        let y = layer.ff(x);
+        layer.bp(y);
+        ParamGroup::new(&["a"]).lr_scale(0.5).update(layer);
+    });
+    crate::rng::reseed(11);
+    let mut halved = Layer::<10>::new();
+    let halved = train(&mut halved, |layer| {
+        let y = layer.ff(x);
+        layer.bp(y);
+        layer.update_params();
+    });
+    for (s, h) in scaled.iter().zip(&halved) {
+        assert!((s - h).abs() < 1e-6);
+    }
+}
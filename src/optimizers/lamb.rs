@@ -0,0 +1,138 @@
+use nalgebra::SMatrix;
+
+use super::component_invsqrt;
+use super::Optimizer;
+use super::OptimizerFactory;
+
+// adam step rescaled to be proportional to the norm of
+// the weights
+pub struct Lamb<
+    const ALPHA_NUM: usize,
+    const ALPHA_DEN: usize,
+    const BETA1_NUM: usize,
+    const BETA1_DEN: usize,
+    const BETA2_NUM: usize,
+    const BETA2_DEN: usize,
+    const R: usize,
+    const C: usize,
+> {
+    m: SMatrix<f32, R, C>,
+    v: SMatrix<f32, R, C>,
+    t: i32,
+}
+
+impl<
+        const ALPHA_NUM: usize,
+        const ALPHA_DEN: usize,
+        const BETA1_NUM: usize,
+        const BETA1_DEN: usize,
+        const BETA2_NUM: usize,
+        const BETA2_DEN: usize,
+        const R: usize,
+        const C: usize,
+    > Optimizer<R, C>
+    for Lamb<
+        ALPHA_NUM,
+        ALPHA_DEN,
+        BETA1_NUM,
+        BETA1_DEN,
+        BETA2_NUM,
+        BETA2_DEN,
+        R,
+        C,
+    >
+{
+    fn init() -> Self {
+        let m = SMatrix::zeros();
+        let v = SMatrix::zeros();
+        let t = 0;
+        Self { m, v, t }
+    }
+
+    fn update_param(
+        &mut self,
+        weight: &mut SMatrix<f32, R, C>,
+        gradient: &SMatrix<f32, R, C>,
+    ) {
+        let alpha = ALPHA_NUM as f32 / ALPHA_DEN as f32;
+        let beta1 = BETA1_NUM as f32 / BETA1_DEN as f32;
+        let beta2 = BETA2_NUM as f32 / BETA2_DEN as f32;
+        self.t += 1;
+
+        self.m = beta1 * self.m + (1. - beta1) * gradient;
+        self.v = beta2 * self.v
+            + (1. - beta2)
+                * gradient.component_mul(gradient);
+
+        let m = self.m / (1. - beta1.powi(self.t));
+        let v = self.v / (1. - beta2.powi(self.t));
+        let r = component_invsqrt(&v).component_mul(&m);
+
+        // layerwise trust ratio
+        let w_norm = weight.norm();
+        let r_norm = r.norm();
+        let trust = if w_norm > 0. && r_norm > 0. {
+            w_norm / r_norm
+        } else {
+            1.
+        };
+
+        *weight -= alpha * trust * r;
+    }
+
+    fn name() -> String {
+        "lamb".to_string()
+    }
+}
+
+pub struct LambFactory<
+    const ALPHA_NUM: usize,
+    const ALPHA_DEN: usize,
+    const BETA1_NUM: usize,
+    const BETA1_DEN: usize,
+    const BETA2_NUM: usize,
+    const BETA2_DEN: usize,
+>;
+
+impl<
+        const R: usize,
+        const C: usize,
+        const ALPHA_NUM: usize,
+        const ALPHA_DEN: usize,
+        const BETA1_NUM: usize,
+        const BETA1_DEN: usize,
+        const BETA2_NUM: usize,
+        const BETA2_DEN: usize,
+    > OptimizerFactory<R, C>
+    for LambFactory<
+        ALPHA_NUM,
+        ALPHA_DEN,
+        BETA1_NUM,
+        BETA1_DEN,
+        BETA2_NUM,
+        BETA2_DEN,
+    >
+{
+    type Optimizer = Lamb<
+        ALPHA_NUM,
+        ALPHA_DEN,
+        BETA1_NUM,
+        BETA1_DEN,
+        BETA2_NUM,
+        BETA2_DEN,
+        R,
+        C,
+    >;
+}
+
+#[test]
+fn test_quadratic_trajectory() {
+    super::assert_quadratic_trajectory::<
+        Lamb<1, 10, 9, 10, 999, 1000, 2, 1>,
+    >(&[
+        [0.920943, -0.420943],
+        [0.849158, -0.349527],
+        [0.783743, -0.285082],
+        [0.723885, -0.227011],
+    ]);
+}
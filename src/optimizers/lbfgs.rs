@@ -0,0 +1,194 @@
+use std::collections::VecDeque;
+
+// limited memory BFGS over a flattened parameter vector (see
+// gather_params / scatter_params), meant for small full batch
+// problems. f returns the loss and its gradient at a point.
+pub struct Lbfgs {
+    // number of (s, y) pairs kept to approximate the hessian
+    history: usize,
+    // gradient norm under which the minimization stops
+    tol: f32,
+    s: VecDeque<Vec<f32>>,
+    y: VecDeque<Vec<f32>>,
+    loss: f32,
+    grad: Vec<f32>,
+}
+
+impl Lbfgs {
+    pub fn new(history: usize, tol: f32) -> Self {
+        Self {
+            history,
+            tol,
+            s: VecDeque::new(),
+            y: VecDeque::new(),
+            loss: f32::INFINITY,
+            grad: Vec::new(),
+        }
+    }
+
+    // one iteration with a line search, returns the loss at the
+    // new x
+    pub fn step<F>(
+        &mut self,
+        x: &mut [f32],
+        f: &mut F,
+    ) -> f32
+    where
+        F: FnMut(&[f32]) -> (f32, Vec<f32>),
+    {
+        const C1: f32 = 1e-4;
+        const C2: f32 = 0.9;
+        const MAX_TRIALS: usize = 50;
+
+        if self.grad.len() != x.len() {
+            (self.loss, self.grad) = f(x);
+            self.s.clear();
+            self.y.clear();
+        }
+
+        let mut d = self.direction();
+        let mut slope = dot(&d, &self.grad);
+        // not a descent direction, fall back to the gradient
+        if slope >= 0. {
+            self.s.clear();
+            self.y.clear();
+            d = self.grad.iter().map(|g| -g).collect();
+            slope = -dot(&self.grad, &self.grad);
+        }
+
+        // weak wolfe line search by bisection
+        let (mut lo, mut hi) = (0., f32::INFINITY);
+        let mut t = 1.;
+        for _ in 0..MAX_TRIALS {
+            let x_new = x
+                .iter()
+                .zip(d.iter())
+                .map(|(xi, di)| xi + t * di)
+                .collect::<Vec<f32>>();
+            let (loss, grad) = f(&x_new);
+            // also rejects steps that blew up to nan
+            if loss.is_nan()
+                || loss > self.loss + C1 * t * slope
+            {
+                // not enough decrease
+                hi = t;
+            } else if dot(&d, &grad) < C2 * slope {
+                // still going down steeply
+                lo = t;
+            } else {
+                let s = sub(&x_new, x);
+                let y = sub(&grad, &self.grad);
+                if dot(&s, &y) > 1e-10 {
+                    self.s.push_back(s);
+                    self.y.push_back(y);
+                    if self.s.len() > self.history {
+                        self.s.pop_front();
+                        self.y.pop_front();
+                    }
+                }
+                x.copy_from_slice(&x_new);
+                self.loss = loss;
+                self.grad = grad;
+                return loss;
+            }
+            t = if hi.is_finite() {
+                (lo + hi) / 2.
+            } else {
+                2. * lo
+            };
+        }
+        // line search failed, start over from the gradient
+        self.s.clear();
+        self.y.clear();
+        self.loss
+    }
+
+    pub fn converged(&self) -> bool {
+        !self.grad.is_empty()
+            && dot(&self.grad, &self.grad).sqrt() < self.tol
+    }
+
+    // returns the final loss
+    pub fn minimize<F>(
+        &mut self,
+        x: &mut [f32],
+        mut f: F,
+        max_iter: usize,
+    ) -> f32
+    where
+        F: FnMut(&[f32]) -> (f32, Vec<f32>),
+    {
+        for _ in 0..max_iter {
+            self.step(x, &mut f);
+            if self.converged() {
+                break;
+            }
+        }
+        self.loss
+    }
+
+    // two loop recursion, -H * grad
+    fn direction(&self) -> Vec<f32> {
+        let mut q = self.grad.clone();
+        let rho = self
+            .s
+            .iter()
+            .zip(self.y.iter())
+            .map(|(s, y)| 1. / dot(s, y))
+            .collect::<Vec<f32>>();
+        let mut alpha = vec![0.; self.s.len()];
+        for i in (0..self.s.len()).rev() {
+            alpha[i] = rho[i] * dot(&self.s[i], &q);
+            axpy(-alpha[i], &self.y[i], &mut q);
+        }
+        if let (Some(s), Some(y)) =
+            (self.s.back(), self.y.back())
+        {
+            let gamma = dot(s, y) / dot(y, y);
+            q.iter_mut().for_each(|qi| *qi *= gamma);
+        }
+        for i in 0..self.s.len() {
+            let beta = rho[i] * dot(&self.y[i], &q);
+            axpy(alpha[i] - beta, &self.s[i], &mut q);
+        }
+        q.iter_mut().for_each(|qi| *qi = -*qi);
+        q
+    }
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b.iter()).map(|(ai, bi)| ai * bi).sum()
+}
+
+fn sub(a: &[f32], b: &[f32]) -> Vec<f32> {
+    a.iter().zip(b.iter()).map(|(ai, bi)| ai - bi).collect()
+}
+
+// y += a * x
+fn axpy(a: f32, x: &[f32], y: &mut [f32]) {
+    y.iter_mut().zip(x.iter()).for_each(|(yi, xi)| {
+        *yi += a * xi;
+    });
+}
+
+#[test]
+fn test_rosenbrock() {
+    let rosenbrock = |x: &[f32]| {
+        let (a, b) = (x[0], x[1]);
+        let loss =
+            (1. - a).powi(2) + 100. * (b - a * a).powi(2);
+        let grad = vec![
+            -2. * (1. - a) - 400. * a * (b - a * a),
+            200. * (b - a * a),
+        ];
+        (loss, grad)
+    };
+    let mut x = [-1.2, 1.];
+    let loss = Lbfgs::new(5, 1e-4)
+        .minimize(&mut x, rosenbrock, 200);
+    assert!(loss < 1e-5, "loss {loss}");
+    assert!(
+        (x[0] - 1.).abs() < 1e-2
+            && (x[1] - 1.).abs() < 1e-2
+    );
+}
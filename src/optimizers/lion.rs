@@ -0,0 +1,126 @@
+use nalgebra::SMatrix;
+
+use super::Optimizer;
+use super::OptimizerFactory;
+
+// only the sign of the interpolated momentum is used, so every
+// coordinate moves by exactly alpha
+pub struct Lion<
+    const ALPHA_NUM: usize,
+    const ALPHA_DEN: usize,
+    const BETA1_NUM: usize,
+    const BETA1_DEN: usize,
+    const BETA2_NUM: usize,
+    const BETA2_DEN: usize,
+    const R: usize,
+    const C: usize,
+> {
+    m: SMatrix<f32, R, C>,
+}
+
+impl<
+        const ALPHA_NUM: usize,
+        const ALPHA_DEN: usize,
+        const BETA1_NUM: usize,
+        const BETA1_DEN: usize,
+        const BETA2_NUM: usize,
+        const BETA2_DEN: usize,
+        const R: usize,
+        const C: usize,
+    > Optimizer<R, C>
+    for Lion<
+        ALPHA_NUM,
+        ALPHA_DEN,
+        BETA1_NUM,
+        BETA1_DEN,
+        BETA2_NUM,
+        BETA2_DEN,
+        R,
+        C,
+    >
+{
+    fn init() -> Self {
+        let m = SMatrix::zeros();
+        Self { m }
+    }
+
+    fn update_param(
+        &mut self,
+        weight: &mut SMatrix<f32, R, C>,
+        gradient: &SMatrix<f32, R, C>,
+    ) {
+        let alpha = ALPHA_NUM as f32 / ALPHA_DEN as f32;
+        let beta1 = BETA1_NUM as f32 / BETA1_DEN as f32;
+        let beta2 = BETA2_NUM as f32 / BETA2_DEN as f32;
+
+        let c = beta1 * self.m + (1. - beta1) * gradient;
+        *weight -= alpha * c.map(sign);
+        self.m = beta2 * self.m + (1. - beta2) * gradient;
+    }
+
+    fn name() -> String {
+        "lion".to_string()
+    }
+}
+
+pub struct LionFactory<
+    const ALPHA_NUM: usize,
+    const ALPHA_DEN: usize,
+    const BETA1_NUM: usize,
+    const BETA1_DEN: usize,
+    const BETA2_NUM: usize,
+    const BETA2_DEN: usize,
+>;
+
+impl<
+        const R: usize,
+        const C: usize,
+        const ALPHA_NUM: usize,
+        const ALPHA_DEN: usize,
+        const BETA1_NUM: usize,
+        const BETA1_DEN: usize,
+        const BETA2_NUM: usize,
+        const BETA2_DEN: usize,
+    > OptimizerFactory<R, C>
+    for LionFactory<
+        ALPHA_NUM,
+        ALPHA_DEN,
+        BETA1_NUM,
+        BETA1_DEN,
+        BETA2_NUM,
+        BETA2_DEN,
+    >
+{
+    type Optimizer = Lion<
+        ALPHA_NUM,
+        ALPHA_DEN,
+        BETA1_NUM,
+        BETA1_DEN,
+        BETA2_NUM,
+        BETA2_DEN,
+        R,
+        C,
+    >;
+}
+
+fn sign(x: f32) -> f32 {
+    if x > 0. {
+        1.
+    } else if x < 0. {
+        -1.
+    } else {
+        0.
+    }
+}
+
+#[test]
+fn test_quadratic_trajectory() {
+    super::assert_quadratic_trajectory::<
+        Lion<1, 10, 9, 10, 99, 100, 2, 1>,
+    >(&[
+        [0.9, -0.4],
+        [0.8, -0.3],
+        [0.7, -0.2],
+        [0.6, -0.1],
+    ]);
+}
@@ -0,0 +1,142 @@
+use std::marker::PhantomData;
+
+use nalgebra::SMatrix;
+
+use super::Optimizer;
+use super::OptimizerFactory;
+
+// Lookahead: the wrapped optimizer moves the fast weights, and
+// every K steps the slow weights move ALPHA of the way towards
+// them, after which the fast weights restart from the slow ones
+pub struct Lookahead<
+    O,
+    const K: usize,
+    const ALPHA_NUM: usize,
+    const ALPHA_DEN: usize,
+    const R: usize,
+    const C: usize,
+> {
+    inner: O,
+    slow: SMatrix<f32, R, C>,
+    t: usize,
+}
+
+impl<
+        O,
+        const K: usize,
+        const ALPHA_NUM: usize,
+        const ALPHA_DEN: usize,
+        const R: usize,
+        const C: usize,
+    > Lookahead<O, K, ALPHA_NUM, ALPHA_DEN, R, C>
+{
+    fn with_inner(inner: O) -> Self {
+        let slow = SMatrix::zeros();
+        let t = 0;
+        Self { inner, slow, t }
+    }
+}
+
+impl<
+        O,
+        const K: usize,
+        const ALPHA_NUM: usize,
+        const ALPHA_DEN: usize,
+        const R: usize,
+        const C: usize,
+    > Optimizer<R, C>
+    for Lookahead<O, K, ALPHA_NUM, ALPHA_DEN, R, C>
+where
+    O: Optimizer<R, C>,
+{
+    fn init() -> Self {
+        Self::with_inner(O::init())
+    }
+
+    fn init_bias() -> Self {
+        Self::with_inner(O::init_bias())
+    }
+
+    fn update_param(
+        &mut self,
+        weight: &mut SMatrix<f32, R, C>,
+        gradient: &SMatrix<f32, R, C>,
+    ) {
+        self.update_param_scaled(weight, gradient, 1.);
+    }
+
+    // only the fast steps are scaled, not the slow weights sync
+    fn update_param_scaled(
+        &mut self,
+        weight: &mut SMatrix<f32, R, C>,
+        gradient: &SMatrix<f32, R, C>,
+        lr_scale: f32,
+    ) {
+        // slow weights start at the weights of the first step
+        if self.t == 0 {
+            self.slow = *weight;
+        }
+        self.inner.update_param_scaled(
+            weight, gradient, lr_scale,
+        );
+        self.t += 1;
+        if self.t.is_multiple_of(K) {
+            let alpha = ALPHA_NUM as f32 / ALPHA_DEN as f32;
+            self.slow += alpha * (*weight - self.slow);
+            *weight = self.slow;
+        }
+    }
+
+    fn finish(&mut self, weight: &mut SMatrix<f32, R, C>) {
+        self.inner.finish(weight);
+    }
+
+    fn name() -> String {
+        format!("Lookahead({})", O::name())
+    }
+}
+
+pub struct LookaheadFactory<
+    O,
+    const K: usize,
+    const ALPHA_NUM: usize,
+    const ALPHA_DEN: usize,
+> {
+    inner: PhantomData<O>,
+}
+
+impl<
+        const R: usize,
+        const C: usize,
+        O,
+        const K: usize,
+        const ALPHA_NUM: usize,
+        const ALPHA_DEN: usize,
+    > OptimizerFactory<R, C>
+    for LookaheadFactory<O, K, ALPHA_NUM, ALPHA_DEN>
+where
+    O: OptimizerFactory<R, C>,
+{
+    type Optimizer = Lookahead<
+        O::Optimizer,
+        K,
+        ALPHA_NUM,
+        ALPHA_DEN,
+        R,
+        C,
+    >;
+}
+
+#[test]
+fn test_quadratic_trajectory() {
+    use super::sgd::Sgd;
+
+    super::assert_quadratic_trajectory::<
+        Lookahead<Sgd<1, 10>, 2, 1, 2, 2, 1>,
+    >(&[
+        [0.9, -0.3],
+        [0.905, -0.34],
+        [0.8145, -0.204],
+        [0.819025, -0.2312],
+    ]);
+}
@@ -1,18 +1,86 @@
 use nalgebra::SMatrix;
+#[cfg(test)]
+use nalgebra::SVector;
 
+pub mod adadelta;
 pub mod adagrad;
 pub mod adam;
+pub mod adamw;
+pub mod amsgrad;
+pub mod clip;
+pub mod ema;
+pub mod groups;
+pub mod lamb;
+pub mod lbfgs;
+pub mod lion;
+pub mod lookahead;
+pub mod momentum;
+pub mod nadam;
+pub mod nesterov;
 pub mod rmsprop;
+pub mod sam;
 pub mod sgd;
 pub mod sgdmomentum;
+pub mod swa;
+pub mod weightdecay;
 
-pub trait Optimizer<const R: usize, const C: usize> {
+pub trait Optimizer<const R: usize, const C: usize>:
+    Sized
+{
     fn init() -> Self;
+    // optimizer for biases, norm and activation parameters,
+    // which weight decay leaves alone
+    fn init_bias() -> Self {
+        Self::init()
+    }
     fn update_param(
         &mut self,
         weight: &mut SMatrix<f32, R, C>,
         gradient: &SMatrix<f32, R, C>,
     );
+    // step with the learning rate multiplied by lr_scale, for
+    // parameter groups. scaling the step of update_param is the
+    // same for optimizers whose step is proportional to their
+    // learning rate, wrappers pass the scale on to the optimizer
+    // they wrap instead
+    fn update_param_scaled(
+        &mut self,
+        weight: &mut SMatrix<f32, R, C>,
+        gradient: &SMatrix<f32, R, C>,
+        lr_scale: f32,
+    ) {
+        if lr_scale == 1. {
+            self.update_param(weight, gradient);
+            return;
+        }
+        let old = *weight;
+        self.update_param(weight, gradient);
+        *weight = old + lr_scale * (*weight - old);
+    }
+    // scaled step of a weight (not a bias) with an l2 penalty of
+    // weight_decay added to its gradient
+    fn update_weight(
+        &mut self,
+        weight: &mut SMatrix<f32, R, C>,
+        gradient: &SMatrix<f32, R, C>,
+        lr_scale: f32,
+        weight_decay: f32,
+    ) {
+        if weight_decay == 0. {
+            self.update_param_scaled(
+                weight, gradient, lr_scale,
+            );
+            return;
+        }
+        let gradient = gradient + weight_decay * *weight;
+        self.update_param_scaled(
+            weight, &gradient, lr_scale,
+        );
+    }
+    // called once training is over, averaging optimizers load
+    // their averaged weights here
+    fn finish(&mut self, _weight: &mut SMatrix<f32, R, C>) {
+    }
     fn name() -> String;
 }
 
@@ -31,3 +99,41 @@ pub fn component_invsqrt<const R: usize, const C: usize>(
     });
     out
 }
+
+// minimizes 0.5 * (w1^2 + 4 * w2^2) starting from (1, -0.5) and
+// compares every step against a trajectory computed by hand
+#[cfg(test)]
+pub fn assert_quadratic_trajectory<O: Optimizer<2, 1>>(
+    expected: &[[f32; 2]],
+) {
+    assert_trajectory(O::init(), expected);
+}
+
+// same with the optimizer of a bias
+#[cfg(test)]
+pub fn assert_bias_trajectory<O: Optimizer<2, 1>>(
+    expected: &[[f32; 2]],
+) {
+    assert_trajectory(O::init_bias(), expected);
+}
+
+#[cfg(test)]
+fn assert_trajectory<O: Optimizer<2, 1>>(
+    mut opt: O,
+    expected: &[[f32; 2]],
+) {
+    let a = SVector::<f32, 2>::new(1., 4.);
+    let mut w = SVector::<f32, 2>::new(1., -0.5);
+    for (t, step) in expected.iter().enumerate() {
+        let g = a.component_mul(&w);
+        opt.update_param(&mut w, &g);
+        assert!(
+            (w - SVector::from(*step)).abs().max() < 1e-4,
+            "{} step {}: got {:?}, expected {:?}",
+            O::name(),
+            t + 1,
+            w.as_slice(),
+            step
+        );
+    }
+}
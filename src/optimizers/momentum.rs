@@ -0,0 +1,92 @@
+use nalgebra::SMatrix;
+
+use super::Optimizer;
+use super::OptimizerFactory;
+
+// classical (heavy ball) momentum:
+// v = mu * v + g, w -= alpha * v
+pub struct Momentum<
+    const ALPHA_NUM: usize,
+    const ALPHA_DEN: usize,
+    const MU_NUM: usize,
+    const MU_DEN: usize,
+    const R: usize,
+    const C: usize,
+> {
+    v: SMatrix<f32, R, C>,
+}
+
+impl<
+        const ALPHA_NUM: usize,
+        const ALPHA_DEN: usize,
+        const MU_NUM: usize,
+        const MU_DEN: usize,
+        const R: usize,
+        const C: usize,
+    > Optimizer<R, C>
+    for Momentum<ALPHA_NUM, ALPHA_DEN, MU_NUM, MU_DEN, R, C>
+{
+    fn init() -> Self {
+        let v = SMatrix::zeros();
+        Self { v }
+    }
+
+    fn update_param(
+        &mut self,
+        weight: &mut SMatrix<f32, R, C>,
+        gradient: &SMatrix<f32, R, C>,
+    ) {
+        let alpha = ALPHA_NUM as f32 / ALPHA_DEN as f32;
+        let mu = MU_NUM as f32 / MU_DEN as f32;
+        self.v = mu * self.v + gradient;
+        *weight -= alpha * self.v;
+    }
+
+    fn name() -> String {
+        "classical momentum".to_string()
+    }
+}
+
+pub struct MomentumFactory<
+    const ALPHA_NUM: usize,
+    const ALPHA_DEN: usize,
+    const MU_NUM: usize,
+    const MU_DEN: usize,
+>;
+
+impl<
+        const R: usize,
+        const C: usize,
+        const ALPHA_NUM: usize,
+        const ALPHA_DEN: usize,
+        const MU_NUM: usize,
+        const MU_DEN: usize,
+    > OptimizerFactory<R, C>
+    for MomentumFactory<
+        ALPHA_NUM,
+        ALPHA_DEN,
+        MU_NUM,
+        MU_DEN,
+    >
+{
+    type Optimizer = Momentum<
+        ALPHA_NUM,
+        ALPHA_DEN,
+        MU_NUM,
+        MU_DEN,
+        R,
+        C,
+    >;
+}
+
+#[test]
+fn test_quadratic_trajectory() {
+    super::assert_quadratic_trajectory::<
+        Momentum<1, 10, 9, 10, 2, 1>,
+    >(&[
+        [0.9, -0.3],
+        [0.72, 0.],
+        [0.486, 0.27],
+        [0.2268, 0.405],
+    ]);
+}
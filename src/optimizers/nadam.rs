@@ -0,0 +1,132 @@
+use nalgebra::SMatrix;
+
+use super::component_invsqrt;
+use super::Optimizer;
+use super::OptimizerFactory;
+
+// adam with a nesterov momentum term
+pub struct Nadam<
+    const ALPHA_NUM: usize,
+    const ALPHA_DEN: usize,
+    const BETA1_NUM: usize,
+    const BETA1_DEN: usize,
+    const BETA2_NUM: usize,
+    const BETA2_DEN: usize,
+    const R: usize,
+    const C: usize,
+> {
+    m: SMatrix<f32, R, C>,
+    v: SMatrix<f32, R, C>,
+    t: i32,
+}
+
+impl<
+        const ALPHA_NUM: usize,
+        const ALPHA_DEN: usize,
+        const BETA1_NUM: usize,
+        const BETA1_DEN: usize,
+        const BETA2_NUM: usize,
+        const BETA2_DEN: usize,
+        const R: usize,
+        const C: usize,
+    > Optimizer<R, C>
+    for Nadam<
+        ALPHA_NUM,
+        ALPHA_DEN,
+        BETA1_NUM,
+        BETA1_DEN,
+        BETA2_NUM,
+        BETA2_DEN,
+        R,
+        C,
+    >
+{
+    fn init() -> Self {
+        let m = SMatrix::zeros();
+        let v = SMatrix::zeros();
+        let t = 0;
+        Self { m, v, t }
+    }
+
+    fn update_param(
+        &mut self,
+        weight: &mut SMatrix<f32, R, C>,
+        gradient: &SMatrix<f32, R, C>,
+    ) {
+        let alpha = ALPHA_NUM as f32 / ALPHA_DEN as f32;
+        let beta1 = BETA1_NUM as f32 / BETA1_DEN as f32;
+        let beta2 = BETA2_NUM as f32 / BETA2_DEN as f32;
+        self.t += 1;
+
+        self.m = beta1 * self.m + (1. - beta1) * gradient;
+        self.v = beta2 * self.v
+            + (1. - beta2)
+                * gradient.component_mul(gradient);
+
+        // nesterov look ahead on the first moment
+        let m = beta1 * self.m
+            / (1. - beta1.powi(self.t + 1))
+            + (1. - beta1) * gradient
+                / (1. - beta1.powi(self.t));
+        let v = self.v / (1. - beta2.powi(self.t));
+
+        *weight -=
+            alpha * component_invsqrt(&v).component_mul(&m);
+    }
+
+    fn name() -> String {
+        "nadam".to_string()
+    }
+}
+
+pub struct NadamFactory<
+    const ALPHA_NUM: usize,
+    const ALPHA_DEN: usize,
+    const BETA1_NUM: usize,
+    const BETA1_DEN: usize,
+    const BETA2_NUM: usize,
+    const BETA2_DEN: usize,
+>;
+
+impl<
+        const R: usize,
+        const C: usize,
+        const ALPHA_NUM: usize,
+        const ALPHA_DEN: usize,
+        const BETA1_NUM: usize,
+        const BETA1_DEN: usize,
+        const BETA2_NUM: usize,
+        const BETA2_DEN: usize,
+    > OptimizerFactory<R, C>
+    for NadamFactory<
+        ALPHA_NUM,
+        ALPHA_DEN,
+        BETA1_NUM,
+        BETA1_DEN,
+        BETA2_NUM,
+        BETA2_DEN,
+    >
+{
+    type Optimizer = Nadam<
+        ALPHA_NUM,
+        ALPHA_DEN,
+        BETA1_NUM,
+        BETA1_DEN,
+        BETA2_NUM,
+        BETA2_DEN,
+        R,
+        C,
+    >;
+}
+
+#[test]
+fn test_quadratic_trajectory() {
+    super::assert_quadratic_trajectory::<
+        Nadam<1, 10, 9, 10, 999, 1000, 2, 1>,
+    >(&[
+        [0.852632, -0.352632],
+        [0.741697, -0.248112],
+        [0.640611, -0.157446],
+        [0.54473, -0.0771646],
+    ]);
+}
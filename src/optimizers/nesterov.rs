@@ -0,0 +1,92 @@
+use nalgebra::SMatrix;
+
+use super::Optimizer;
+use super::OptimizerFactory;
+
+// nesterov momentum, the gradient is taken at the look
+// ahead point w - alpha * mu * v
+pub struct Nesterov<
+    const ALPHA_NUM: usize,
+    const ALPHA_DEN: usize,
+    const MU_NUM: usize,
+    const MU_DEN: usize,
+    const R: usize,
+    const C: usize,
+> {
+    v: SMatrix<f32, R, C>,
+}
+
+impl<
+        const ALPHA_NUM: usize,
+        const ALPHA_DEN: usize,
+        const MU_NUM: usize,
+        const MU_DEN: usize,
+        const R: usize,
+        const C: usize,
+    > Optimizer<R, C>
+    for Nesterov<ALPHA_NUM, ALPHA_DEN, MU_NUM, MU_DEN, R, C>
+{
+    fn init() -> Self {
+        let v = SMatrix::zeros();
+        Self { v }
+    }
+
+    fn update_param(
+        &mut self,
+        weight: &mut SMatrix<f32, R, C>,
+        gradient: &SMatrix<f32, R, C>,
+    ) {
+        let alpha = ALPHA_NUM as f32 / ALPHA_DEN as f32;
+        let mu = MU_NUM as f32 / MU_DEN as f32;
+        self.v = mu * self.v + gradient;
+        *weight -= alpha * (gradient + mu * self.v);
+    }
+
+    fn name() -> String {
+        "nesterov".to_string()
+    }
+}
+
+pub struct NesterovFactory<
+    const ALPHA_NUM: usize,
+    const ALPHA_DEN: usize,
+    const MU_NUM: usize,
+    const MU_DEN: usize,
+>;
+
+impl<
+        const R: usize,
+        const C: usize,
+        const ALPHA_NUM: usize,
+        const ALPHA_DEN: usize,
+        const MU_NUM: usize,
+        const MU_DEN: usize,
+    > OptimizerFactory<R, C>
+    for NesterovFactory<
+        ALPHA_NUM,
+        ALPHA_DEN,
+        MU_NUM,
+        MU_DEN,
+    >
+{
+    type Optimizer = Nesterov<
+        ALPHA_NUM,
+        ALPHA_DEN,
+        MU_NUM,
+        MU_DEN,
+        R,
+        C,
+    >;
+}
+
+#[test]
+fn test_quadratic_trajectory() {
+    super::assert_quadratic_trajectory::<
+        Nesterov<1, 10, 9, 10, 2, 1>,
+    >(&[
+        [0.81, -0.12],
+        [0.5751, 0.1332],
+        [0.327321, 0.216648],
+        [0.0938879, 0.175051],
+    ]);
+}
@@ -0,0 +1,97 @@
+use super::clip::global_norm;
+use crate::layers::Parameters;
+
+// sharpness-aware minimization: the gradients used for the step
+// are computed at w + rho * g / |g| instead of at w, so training
+// has to run a second feedforward/backprop between perturb and
+// restore
+pub struct Sam {
+    rho: f32,
+    eps: Vec<f32>,
+}
+
+impl Sam {
+    pub fn new(rho: f32) -> Self {
+        let eps = Vec::new();
+        Self { rho, eps }
+    }
+
+    // moves the weights up the current gradients
+    pub fn perturb<P: Parameters + ?Sized>(
+        &mut self,
+        params: &mut P,
+    ) {
+        const EPSILON: f32 = 1e-12;
+
+        let scale =
+            self.rho / (global_norm(params) + EPSILON);
+        self.eps.clear();
+        params.visit_params(&mut |w, g| {
+            w.iter_mut().zip(g.iter()).for_each(
+                |(wi, gi)| {
+                    let e = scale * gi;
+                    *wi += e;
+                    self.eps.push(e);
+                },
+            );
+        });
+    }
+
+    // moves the weights back, keeping the gradients computed at
+    // the perturbed point
+    pub fn restore<P: Parameters + ?Sized>(
+        &mut self,
+        params: &mut P,
+    ) {
+        let mut eps = self.eps.iter();
+        params.visit_params(&mut |w, _| {
+            w.iter_mut()
+                .zip(&mut eps)
+                .for_each(|(wi, e)| *wi -= e);
+        });
+    }
+}
+
+#[test]
+fn test_sam_perturbation() {
+    use nalgebra::SVector;
+
+    use crate::activation::noact::NoActivation;
+    use crate::layers::sequential::Sequential;
+    use crate::optimizers::sgd::SgdFactory;
+
+    let mut layer = Sequential::<
+        3,
+        2,
+        NoActivation,
+        SgdFactory<1, 1>,
+    >::new();
+    let params = |layer: &mut Sequential<_, _, _, _>| {
+        let mut p = Vec::new();
+        layer.visit_params(&mut |w, _| {
+            p.extend_from_slice(w)
+        });
+        p
+    };
+    layer.ff(SVector::<f32, 3>::new(1., 2., 3.));
+    layer.bp(SVector::<f32, 2>::new(4., -5.));
+
+    let w0 = params(&mut layer);
+    let mut sam = Sam::new(0.05);
+    sam.perturb(&mut layer);
+    let w1 = params(&mut layer);
+    let dist = w0
+        .iter()
+        .zip(w1.iter())
+        .map(|(a, b)| (a - b) * (a - b))
+        .sum::<f32>()
+        .sqrt();
+    assert!((dist - 0.05).abs() < 1e-5);
+
+    sam.restore(&mut layer);
+    let w2 = params(&mut layer);
+    assert!(w0
+        .iter()
+        .zip(w2.iter())
+        .all(|(a, b)| (a - b).abs() < 1e-6));
+}
@@ -0,0 +1,181 @@
+use std::marker::PhantomData;
+
+use nalgebra::SMatrix;
+
+use super::Optimizer;
+use super::OptimizerFactory;
+
+// stochastic weight averaging: the wrapped optimizer runs as is
+// for the first START steps, then its steps are scaled by a
+// cyclic schedule going linearly from 1 down to LR_MIN every
+// CYCLE steps, and the weights at the end of each cycle are
+// averaged. finish loads the average into the weights.
+pub struct Swa<
+    O,
+    const START: usize,
+    const CYCLE: usize,
+    const LR_MIN_NUM: usize,
+    const LR_MIN_DEN: usize,
+    const R: usize,
+    const C: usize,
+> {
+    inner: O,
+    avg: SMatrix<f32, R, C>,
+    n: usize,
+    t: usize,
+}
+
+impl<
+        O,
+        const START: usize,
+        const CYCLE: usize,
+        const LR_MIN_NUM: usize,
+        const LR_MIN_DEN: usize,
+        const R: usize,
+        const C: usize,
+    > Swa<O, START, CYCLE, LR_MIN_NUM, LR_MIN_DEN, R, C>
+{
+    fn with_inner(inner: O) -> Self {
+        let avg = SMatrix::zeros();
+        let n = 0;
+        let t = 0;
+        Self { inner, avg, n, t }
+    }
+}
+
+impl<
+        O,
+        const START: usize,
+        const CYCLE: usize,
+        const LR_MIN_NUM: usize,
+        const LR_MIN_DEN: usize,
+        const R: usize,
+        const C: usize,
+    > Optimizer<R, C>
+    for Swa<O, START, CYCLE, LR_MIN_NUM, LR_MIN_DEN, R, C>
+where
+    O: Optimizer<R, C>,
+{
+    fn init() -> Self {
+        Self::with_inner(O::init())
+    }
+
+    fn init_bias() -> Self {
+        Self::with_inner(O::init_bias())
+    }
+
+    fn update_param(
+        &mut self,
+        weight: &mut SMatrix<f32, R, C>,
+        gradient: &SMatrix<f32, R, C>,
+    ) {
+        self.update_param_scaled(weight, gradient, 1.);
+    }
+
+    // lr_scale multiplies the cyclic schedule, the averaging is
+    // left alone
+    fn update_param_scaled(
+        &mut self,
+        weight: &mut SMatrix<f32, R, C>,
+        gradient: &SMatrix<f32, R, C>,
+        lr_scale: f32,
+    ) {
+        self.t += 1;
+        if self.t <= START {
+            self.inner.update_param_scaled(
+                weight, gradient, lr_scale,
+            );
+            return;
+        }
+        let lr_min = LR_MIN_NUM as f32 / LR_MIN_DEN as f32;
+        let c = (self.t - START - 1) % CYCLE;
+        let scale = 1.
+            + (lr_min - 1.) * (c + 1) as f32 / CYCLE as f32;
+        self.inner.update_param_scaled(
+            weight,
+            gradient,
+            scale * lr_scale,
+        );
+        // end of cycle
+        if c == CYCLE - 1 {
+            self.n += 1;
+            self.avg +=
+                (*weight - self.avg) / self.n as f32;
+        }
+    }
+
+    fn finish(&mut self, weight: &mut SMatrix<f32, R, C>) {
+        self.inner.finish(weight);
+        if self.n > 0 {
+            *weight = self.avg;
+        }
+    }
+
+    fn name() -> String {
+        format!("SWA({})", O::name())
+    }
+}
+
+pub struct SwaFactory<
+    O,
+    const START: usize,
+    const CYCLE: usize,
+    const LR_MIN_NUM: usize,
+    const LR_MIN_DEN: usize,
+> {
+    inner: PhantomData<O>,
+}
+
+impl<
+        const R: usize,
+        const C: usize,
+        O,
+        const START: usize,
+        const CYCLE: usize,
+        const LR_MIN_NUM: usize,
+        const LR_MIN_DEN: usize,
+    > OptimizerFactory<R, C>
+    for SwaFactory<O, START, CYCLE, LR_MIN_NUM, LR_MIN_DEN>
+where
+    O: OptimizerFactory<R, C>,
+{
+    type Optimizer = Swa<
+        O::Optimizer,
+        START,
+        CYCLE,
+        LR_MIN_NUM,
+        LR_MIN_DEN,
+        R,
+        C,
+    >;
+}
+
+#[test]
+fn test_quadratic_trajectory() {
+    use nalgebra::SVector;
+
+    use super::sgd::Sgd;
+
+    type O = Swa<Sgd<1, 10>, 1, 2, 1, 2, 2, 1>;
+
+    super::assert_quadratic_trajectory::<O>(&[
+        [0.9, -0.3],
+        [0.8325, -0.21],
+        [0.790875, -0.168],
+        [0.7315594, -0.1176],
+        [0.6949814, -0.09408],
+    ]);
+
+    // average of the weights at the end of both cycles
+    let mut opt = O::init();
+    let mut w = SVector::<f32, 2>::new(1., -0.5);
+    for _ in 0..5 {
+        let g = SVector::<f32, 2>::new(1., 4.)
+            .component_mul(&w);
+        opt.update_param(&mut w, &g);
+    }
+    opt.finish(&mut w);
+    let expected =
+        SVector::<f32, 2>::new(0.7429282, -0.13104);
+    assert!((w - expected).abs().max() < 1e-5);
+}
@@ -0,0 +1,306 @@
+use std::marker::PhantomData;
+
+use nalgebra::SMatrix;
+
+use super::Optimizer;
+use super::OptimizerFactory;
+
+// L2 regularization: WD * w is added to the gradient before the
+// wrapped optimizer sees it. With EXCLUDE_BIAS the optimizers of
+// biases and norm/activation parameters are left untouched.
+pub struct L2<
+    O,
+    const WD_NUM: usize,
+    const WD_DEN: usize,
+    const EXCLUDE_BIAS: bool,
+    const R: usize,
+    const C: usize,
+> {
+    inner: O,
+    decay: bool,
+}
+
+impl<
+        O,
+        const WD_NUM: usize,
+        const WD_DEN: usize,
+        const EXCLUDE_BIAS: bool,
+        const R: usize,
+        const C: usize,
+    > Optimizer<R, C>
+    for L2<O, WD_NUM, WD_DEN, EXCLUDE_BIAS, R, C>
+where
+    O: Optimizer<R, C>,
+{
+    fn init() -> Self {
+        let inner = O::init();
+        let decay = true;
+        Self { inner, decay }
+    }
+
+    fn init_bias() -> Self {
+        let inner = O::init_bias();
+        let decay = !EXCLUDE_BIAS;
+        Self { inner, decay }
+    }
+
+    fn update_param(
+        &mut self,
+        weight: &mut SMatrix<f32, R, C>,
+        gradient: &SMatrix<f32, R, C>,
+    ) {
+        self.update_param_scaled(weight, gradient, 1.);
+    }
+
+    fn update_param_scaled(
+        &mut self,
+        weight: &mut SMatrix<f32, R, C>,
+        gradient: &SMatrix<f32, R, C>,
+        lr_scale: f32,
+    ) {
+        if self.decay {
+            let wd = WD_NUM as f32 / WD_DEN as f32;
+            let gradient = gradient + wd * *weight;
+            self.inner.update_param_scaled(
+                weight, &gradient, lr_scale,
+            );
+        } else {
+            self.inner.update_param_scaled(
+                weight, gradient, lr_scale,
+            );
+        }
+    }
+
+    fn finish(&mut self, weight: &mut SMatrix<f32, R, C>) {
+        self.inner.finish(weight);
+    }
+
+    fn name() -> String {
+        format!("{} + L2", O::name())
+    }
+}
+
+pub struct L2Factory<
+    O,
+    const WD_NUM: usize,
+    const WD_DEN: usize,
+    const EXCLUDE_BIAS: bool,
+> {
+    inner: PhantomData<O>,
+}
+
+impl<
+        const R: usize,
+        const C: usize,
+        O,
+        const WD_NUM: usize,
+        const WD_DEN: usize,
+        const EXCLUDE_BIAS: bool,
+    > OptimizerFactory<R, C>
+    for L2Factory<O, WD_NUM, WD_DEN, EXCLUDE_BIAS>
+where
+    O: OptimizerFactory<R, C>,
+{
+    type Optimizer = L2<
+        O::Optimizer,
+        WD_NUM,
+        WD_DEN,
+        EXCLUDE_BIAS,
+        R,
+        C,
+    >;
+}
+
+// decoupled weight decay (AdamW style): after the wrapped
+// optimizer step the weights shrink by w -= WD * w, so WD is the
+// per step decay (learning rate times decay coefficient)
+pub struct DecoupledDecay<
+    O,
+    const WD_NUM: usize,
+    const WD_DEN: usize,
+    const EXCLUDE_BIAS: bool,
+    const R: usize,
+    const C: usize,
+> {
+    inner: O,
+    decay: bool,
+}
+
+impl<
+        O,
+        const WD_NUM: usize,
+        const WD_DEN: usize,
+        const EXCLUDE_BIAS: bool,
+        const R: usize,
+        const C: usize,
+    > Optimizer<R, C>
+    for DecoupledDecay<
+        O,
+        WD_NUM,
+        WD_DEN,
+        EXCLUDE_BIAS,
+        R,
+        C,
+    >
+where
+    O: Optimizer<R, C>,
+{
+    fn init() -> Self {
+        let inner = O::init();
+        let decay = true;
+        Self { inner, decay }
+    }
+
+    fn init_bias() -> Self {
+        let inner = O::init_bias();
+        let decay = !EXCLUDE_BIAS;
+        Self { inner, decay }
+    }
+
+    fn update_param(
+        &mut self,
+        weight: &mut SMatrix<f32, R, C>,
+        gradient: &SMatrix<f32, R, C>,
+    ) {
+        self.update_param_scaled(weight, gradient, 1.);
+    }
+
+    // the decay is per step, so it is scaled like the step
+    fn update_param_scaled(
+        &mut self,
+        weight: &mut SMatrix<f32, R, C>,
+        gradient: &SMatrix<f32, R, C>,
+        lr_scale: f32,
+    ) {
+        let old = *weight;
+        self.inner.update_param_scaled(
+            weight, gradient, lr_scale,
+        );
+        if self.decay {
+            let wd = WD_NUM as f32 / WD_DEN as f32;
+            *weight -= lr_scale * wd * old;
+        }
+    }
+
+    fn finish(&mut self, weight: &mut SMatrix<f32, R, C>) {
+        self.inner.finish(weight);
+    }
+
+    fn name() -> String {
+        format!("{} + decoupled weight decay", O::name())
+    }
+}
+
+pub struct DecoupledDecayFactory<
+    O,
+    const WD_NUM: usize,
+    const WD_DEN: usize,
+    const EXCLUDE_BIAS: bool,
+> {
+    inner: PhantomData<O>,
+}
+
+impl<
+        const R: usize,
+        const C: usize,
+        O,
+        const WD_NUM: usize,
+        const WD_DEN: usize,
+        const EXCLUDE_BIAS: bool,
+    > OptimizerFactory<R, C>
+    for DecoupledDecayFactory<
+        O,
+        WD_NUM,
+        WD_DEN,
+        EXCLUDE_BIAS,
+    >
+where
+    O: OptimizerFactory<R, C>,
+{
+    type Optimizer = DecoupledDecay<
+        O::Optimizer,
+        WD_NUM,
+        WD_DEN,
+        EXCLUDE_BIAS,
+        R,
+        C,
+    >;
+}
+
+#[test]
+fn test_l2() {
+    use super::sgd::Sgd;
+
+    // g + 0.5 w, then a 0.1 sgd step
+    let decayed = [[0.85, -0.275], [0.7225, -0.15125]];
+    // plain sgd
+    let plain = [[0.9, -0.3], [0.81, -0.18]];
+    super::assert_quadratic_trajectory::<
+        L2<Sgd<1, 10>, 1, 2, true, 2, 1>,
+    >(&decayed);
+    super::assert_bias_trajectory::<
+        L2<Sgd<1, 10>, 1, 2, true, 2, 1>,
+    >(&plain);
+    super::assert_bias_trajectory::<
+        L2<Sgd<1, 10>, 1, 2, false, 2, 1>,
+    >(&decayed);
+}
+
+#[test]
+fn test_decoupled_decay() {
+    use super::sgd::Sgd;
+
+    // a 0.1 sgd step, then w -= 0.1 w
+    let decayed = [[0.8, -0.25], [0.64, -0.125]];
+    let plain = [[0.9, -0.3], [0.81, -0.18]];
+    super::assert_quadratic_trajectory::<
+        DecoupledDecay<Sgd<1, 10>, 1, 10, true, 2, 1>,
+    >(&decayed);
+    super::assert_bias_trajectory::<
+        DecoupledDecay<Sgd<1, 10>, 1, 10, true, 2, 1>,
+    >(&plain);
+    super::assert_bias_trajectory::<
+        DecoupledDecay<Sgd<1, 10>, 1, 10, false, 2, 1>,
+    >(&decayed);
+}
+
+#[test]
+fn test_decay_excludes_layer_biases() {
+    use nalgebra::SVector;
+
+    use super::sgd::SgdFactory;
+    use crate::activation::noact::NoActivation;
+    use crate::layers::sequential::Sequential;
+    use crate::layers::Parameters;
+
+    let mut layer = Sequential::<
+        2,
+        2,
+        NoActivation,
+        DecoupledDecayFactory<
+            SgdFactory<1, 10>,
+            1,
+            10,
+            true,
+        >,
+    >::new();
+    let params = |layer: &mut Sequential<_, _, _, _>| {
+        let mut p = Vec::new();
+        layer.visit_params(&mut |w, _| {
+            p.extend_from_slice(w)
+        });
+        p
+    };
+    // zero gradients, so only the decay moves the parameters
+    layer.ff(SVector::<f32, 2>::new(1., -1.));
+    layer.bp(SVector::zeros());
+    let before = params(&mut layer);
+    layer.update_params();
+    let after = params(&mut layer);
+    // the 4 weights first, then the 2 biases
+    for i in 0..6 {
+        let expected =
+            if i < 4 { 0.9 * before[i] } else { before[i] };
+        assert!((after[i] - expected).abs() < 1e-6);
+    }
+}
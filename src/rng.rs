@@ -0,0 +1,67 @@
+use std::cell::RefCell;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+
+use rand::rngs::StdRng;
+use rand::RngCore;
+use rand::SeedableRng;
+
+// every random draw (weight init, embeddings, dropout...) goes
+// through the thread local generator below, which every thread
+// seeds from SEED the first time it is used
+static SEED: AtomicU64 = AtomicU64::new(0);
+
+thread_local! {
+    static RNG: RefCell<StdRng> = RefCell::new(
+        StdRng::seed_from_u64(SEED.load(Ordering::Relaxed)),
+    );
+}
+
+// sets the seed of the whole program, call it before spawning
+// any thread
+pub fn set_seed(seed: u64) {
+    SEED.store(seed, Ordering::Relaxed);
+    reseed(seed);
+}
+
+pub fn seed() -> u64 {
+    SEED.load(Ordering::Relaxed)
+}
+
+// reseeds the generator of the calling thread only, so that
+// parallel tasks can each get their own deterministic stream
+pub fn reseed(seed: u64) {
+    RNG.with(|rng| {
+        *rng.borrow_mut() = StdRng::seed_from_u64(seed);
+    });
+}
+
+// seed of the i-th of several parallel workers, drawn from a
+// generator seeded with seed so that the workers of nearby seeds
+// do not share streams
+pub fn worker_seed(seed: u64, i: usize) -> u64 {
+    let mut seeds = StdRng::seed_from_u64(seed);
+    for _ in 0..i {
+        seeds.next_u64();
+    }
+    seeds.next_u64()
+}
+
+pub fn with_rng<T>(f: impl FnOnce(&mut StdRng) -> T) -> T {
+    RNG.with(|rng| f(&mut rng.borrow_mut()))
+}
+
+#[test]
+fn test_worker_seed() {
+    let seeds = (0..4)
+        .map(|i| worker_seed(u64::MAX, i))
+        .collect::<Vec<_>>();
+    assert_eq!(seeds[2], worker_seed(u64::MAX, 2));
+    for i in 0..4 {
+        for j in 0..i {
+            assert_ne!(seeds[i], seeds[j]);
+        }
+    }
+    // worker 1 of a seed is not worker 0 of the next one
+    assert_ne!(worker_seed(7, 1), worker_seed(8, 0));
+}
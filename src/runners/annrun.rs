@@ -2,17 +2,24 @@ use std::sync::mpsc::Sender;
 use std::sync::mpsc::{self};
 
 use super::write_costs_to_file;
+use crate::activation::noact::NoActivation;
+use crate::activation::prelu::PRelu;
 use crate::activation::relu::Relu;
 use crate::activation::sigmoid::Sigmoid;
+use crate::activation::swish::Swish;
 use crate::activation::ActivationFunction;
+use crate::activation::ParametricActivation;
 use crate::dataset::get_data_csv;
 use crate::loss::crossent::CrossEntropy;
 use crate::loss::LossFunction;
 use crate::models::ann::Ann;
 use crate::models::ann4::Ann4;
+use crate::models::annparam::AnnParamAct;
 use crate::models::NNClassifierModel;
 use crate::optimizers::adam::AdamFactory;
+use crate::optimizers::lbfgs::Lbfgs;
 use crate::optimizers::rmsprop::RmsPropFactory;
+use crate::optimizers::sgd::SgdFactory;
 use crate::optimizers::sgdmomentum::SgdWMomentumFactory;
 use crate::optimizers::Optimizer;
 use crate::optimizers::OptimizerFactory;
@@ -36,6 +43,7 @@ fn train_and_validate<
 >(
     csv_file: &str,
     debug_channel: Option<Sender<f32>>,
+    lbfgs: Option<Lbfgs>,
 ) {
     let (x_train, y_train, x_test, y_test) =
         get_data_csv(csv_file, 0.8)
@@ -63,18 +71,101 @@ fn train_and_validate<
         LOSS,
         OPT,
     >::preprocess(&x_test, &y_test);
+    let (name, score) = match lbfgs {
+        // the layer norm in Ann only backpropagates an
+        // approximation of the gradient, which the line search
+        // can't work with
+        Some(mut lbfgs) => {
+            let mut model =
+                NNClassifierModel::<
+                    Ann4<L1, L2, L3, L4, F1, F2, LOSS, OPT>,
+                    L4,
+                >::new(debug_channel);
+            model.train_lbfgs(
+                &x_train, &y_train, &mut lbfgs, 200,
+            );
+            let score = model.validate(&x_test, &y_test);
+            ("L-BFGS".to_string(), score)
+        }
+        None => {
+            let mut model =
+                NNClassifierModel::<
+                    //Ann4<L1, L2, L3, L4, F1, F2, LOSS, OPT>,
+                    Ann<L1, L4, L2, 5, F1, LOSS, OPT>,
+                    L4,
+                >::new(debug_channel);
+            model.train(&x_train, &y_train);
+            model.finish();
+            let score = model.validate(&x_test, &y_test);
+            let name =
+                <OPT as OptimizerFactory<L2, 1>>::Optimizer::name(
+                );
+            (name, score)
+        }
+    };
+
+    println!(
+        "File: {}\t LR:{}\t Score: {:.3}%\t",
+        csv_file,
+        name,
+        score * 100.
+    );
+}
+
+// the hidden activations P1 and P2 are trained with the weights
+fn train_and_validate_param_act<
+    const L1: usize,
+    const L2: usize,
+    const L3: usize,
+    const L4: usize,
+    P1: ParametricActivation,
+    P2: ParametricActivation,
+    LOSS: LossFunction<L4>,
+    OPT: OptimizerFactory<L2, L1>
+        + OptimizerFactory<L2, 1>
+        + OptimizerFactory<L3, L2>
+        + OptimizerFactory<L3, 1>
+        + OptimizerFactory<L4, L3>
+        + OptimizerFactory<L4, 1>,
+>(
+    csv_file: &str,
+    debug_channel: Option<Sender<f32>>,
+) {
+    let (x_train, y_train, x_test, y_test) =
+        get_data_csv(csv_file, 0.8)
+            .expect("Could not read data from csv file");
+    let (x_train, y_train) =
+        Ann4::<
+            L1,
+            L2,
+            L3,
+            L4,
+            NoActivation,
+            NoActivation,
+            LOSS,
+            OPT,
+        >::preprocess(&x_train, &y_train);
+    let (x_test, _) = Ann4::<
+        L1,
+        L2,
+        L3,
+        L4,
+        NoActivation,
+        NoActivation,
+        LOSS,
+        OPT,
+    >::preprocess(&x_test, &y_test);
     let mut model = NNClassifierModel::<
-        //Ann4<L1, L2, L3, L4, F1, F2, LOSS, OPT>,
-        Ann<L1, L4, L2, 5, F1, LOSS, OPT>,
+        AnnParamAct<L1, L2, L3, L4, P1, P2, LOSS, OPT>,
         L4,
     >::new(debug_channel);
     model.train(&x_train, &y_train);
+    model.finish();
     let score = model.validate(&x_test, &y_test);
 
     println!(
-        "File: {}\t LR:{}\t Score: {:.3}%\t",
+        "File: {}\t Learnt activations\t Score: {:.3}%\t",
         csv_file,
-        <OPT as OptimizerFactory<L2, 1>>::Optimizer::name(),
         score * 100.
     );
 }
@@ -93,8 +184,9 @@ pub fn train_and_validate_csv_ann() {
                 CrossEntropy,
                 //SgdFactory<8, 10>,
                 //RmsPropFactory<8, 10, 9, 10>,
+                //LookaheadFactory<AdamFactory<1, 100, 9, 10, 9, 10>, 5, 1, 2>,
                 AdamFactory<1, 100, 9, 10, 9, 10>,
-            >("data/knn.csv", Some(tx));
+            >("data/knn.csv", Some(tx), None);
             write_costs_to_file("knn.csv", rx);
         },
         || {
@@ -110,7 +202,7 @@ pub fn train_and_validate_csv_ann() {
                 //SgdWMomentumFactory<1, 10, 8, 10>,
                 //RmsPropFactory<1, 10, 9, 10>,
                 AdamFactory<1, 100, 9, 10, 9, 10>,
-            >("data/gda.csv", Some(tx));
+            >("data/gda.csv", Some(tx), None);
             write_costs_to_file("gda.csv", rx);
         },
         || {
@@ -127,7 +219,7 @@ pub fn train_and_validate_csv_ann() {
                 //SgdFactory<1, 1000>,
                 RmsPropFactory<1, 1000, 9, 10>,
                 //AdamFactory<1, 1000, 8, 10, 8, 10>,
-            >("data/nb.csv", Some(tx));
+            >("data/nb.csv", Some(tx), None);
             write_costs_to_file("nb.csv", rx);
         },
         || {
@@ -143,7 +235,9 @@ pub fn train_and_validate_csv_ann() {
                 //SgdFactory<1, 2>,
                 //RmsPropFactory<1, 2, 9, 10>,
                 AdamFactory<1, 2, 8, 10, 8, 10>,
-            >("data/neg_square.csv", Some(tx));
+            >(
+                "data/neg_square.csv", Some(tx), None
+            );
             write_costs_to_file("neg_square.csv", rx);
         },
         || {
@@ -159,9 +253,23 @@ pub fn train_and_validate_csv_ann() {
                 //SgdFactory<1, 2>,
                 SgdWMomentumFactory<1, 2, 8, 10>,
                 //AdamFactory<1, 2, 8, 10, 8, 10>,
-            >("data/circle.csv", Some(tx));
+            >("data/circle.csv", Some(tx), None);
             write_costs_to_file("circle.csv", rx);
         },
+        || {
+            let (tx, rx) = mpsc::channel();
+            train_and_validate_param_act::<
+                2,
+                15,
+                13,
+                2,
+                PRelu,
+                Swish,
+                CrossEntropy,
+                AdamFactory<1, 100, 9, 10, 9, 10>,
+            >("data/circle.csv", Some(tx));
+            write_costs_to_file("circle-paramact.csv", rx);
+        },
     ];
     tasks
         .into_iter()
@@ -170,3 +278,85 @@ pub fn train_and_validate_csv_ann() {
             task.join().expect("A task failed")
         });
 }
+
+// full batch L-BFGS on the small datasets, the optimizer factory
+// is only there to build the models
+pub fn train_and_validate_csv_ann_lbfgs() {
+    let tasks = vec![
+        || {
+            let (tx, rx) = mpsc::channel();
+            train_and_validate::<
+                2,
+                10,
+                6,
+                3,
+                Relu,
+                Sigmoid,
+                CrossEntropy,
+                SgdFactory<1, 1>,
+            >(
+                "data/knn.csv",
+                Some(tx),
+                Some(Lbfgs::new(10, 1e-5)),
+            );
+            write_costs_to_file("knn-lbfgs.csv", rx);
+        },
+        || {
+            let (tx, rx) = mpsc::channel();
+            train_and_validate::<
+                2,
+                7,
+                10,
+                3,
+                Relu,
+                Sigmoid,
+                CrossEntropy,
+                SgdFactory<1, 1>,
+            >(
+                "data/gda.csv",
+                Some(tx),
+                Some(Lbfgs::new(10, 1e-5)),
+            );
+            write_costs_to_file("gda-lbfgs.csv", rx);
+        },
+        || {
+            let (tx, rx) = mpsc::channel();
+            train_and_validate::<
+                2,
+                12,
+                14,
+                2,
+                Relu,
+                Sigmoid,
+                CrossEntropy,
+                SgdFactory<1, 1>,
+            >(
+                "data/neg_square.csv",
+                Some(tx),
+                Some(Lbfgs::new(10, 1e-5)),
+            );
+            write_costs_to_file("neg_square-lbfgs.csv", rx);
+        },
+        || {
+            let (tx, rx) = mpsc::channel();
+            train_and_validate::<
+                2,
+                15,
+                13,
+                2,
+                Relu,
+                Sigmoid,
+                CrossEntropy,
+                SgdFactory<1, 1>,
+            >(
+                "data/circle.csv",
+                Some(tx),
+                Some(Lbfgs::new(10, 1e-5)),
+            );
+            write_costs_to_file("circle-lbfgs.csv", rx);
+        },
+    ];
+    tasks.into_iter().map(std::thread::spawn).for_each(
+        |task| task.join().expect("A task failed"),
+    );
+}
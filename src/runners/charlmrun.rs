@@ -0,0 +1,146 @@
+use std::sync::mpsc;
+
+use crate::activation::tanh::Tanh;
+use crate::dataset::get_data_text;
+use crate::initializers::constant::Zeros;
+use crate::initializers::orthogonal::Orthogonal;
+use crate::initializers::xavier::XavierUniform;
+use crate::layers::lstm::Lstm;
+use crate::layers::rnncell::RnnCell;
+use crate::layers::Parameters;
+use crate::layers::Recurrent;
+use crate::models::charlm::CharLm;
+use crate::models::charlm::CharVocab;
+use crate::models::charlm::H;
+use crate::models::charlm::T;
+use crate::models::charlm::V;
+use crate::models::sampling::Sampler;
+use crate::optimizers::adam::AdamFactory;
+use crate::optimizers::clip::GradClip;
+use crate::rng;
+use crate::runners::write_costs_to_file;
+
+type O = AdamFactory<1, 1000, 9, 10, 999, 1000>;
+
+const HV: usize = H + V;
+// trains one model on the train text and evaluates it on the test
+// text
+type Task = fn(&CharVocab, &[usize], &[usize]);
+const EPOCHS: usize = 5;
+const GENERATED: usize = 200;
+
+fn train_and_validate<R: Recurrent<V, H, T>>(
+    name: &str,
+    rnn: R,
+    vocab: &CharVocab,
+    train: &[usize],
+    test: &[usize],
+) {
+    let (tx, rx) = mpsc::channel();
+    let dbg_thread = {
+        let file = format!("charlm-{name}.txt");
+        std::thread::spawn(move || {
+            write_costs_to_file(&file, rx)
+        })
+    };
+    let mut model = CharLm::<R, O>::new(rnn, vocab.len());
+    let clip = GradClip::GlobalNorm(5.);
+    for epoch in 0..EPOCHS {
+        // the windows follow each other, so the state carries over
+        // and only the gradients are truncated
+        model.reset_state();
+        let mut cost = 0.;
+        let windows = (0..train.len() - 1).step_by(T);
+        for (i, start) in windows.enumerate() {
+            let end = (start + T).min(train.len() - 1);
+            cost += model.train_step(
+                &train[start..end],
+                &train[start + 1..end + 1],
+            );
+            clip.apply(&mut model);
+            model.update_params();
+            if (i + 1) % 100 == 0 {
+                tx.send(cost / 100.).unwrap();
+                cost = 0.;
+            }
+        }
+        println!("{name}: epoch {epoch} done");
+    }
+    model.finish_params();
+    drop(tx);
+
+    let loss = model.loss(test);
+    println!(
+        "Char LM {name}, test perplexity: {:.3}, bits per \
+         char: {:.3}",
+        loss.exp(),
+        loss / std::f32::consts::LN_2
+    );
+    let prompt = &test[..T.min(test.len())];
+    let samplers = [
+        ("greedy", Sampler::new().temperature(0.)),
+        ("t=0.8", Sampler::new().temperature(0.8)),
+        ("top-k 5", Sampler::new().top_k(5)),
+        ("top-p 0.9", Sampler::new().top_p(0.9)),
+    ];
+    for (sampler_name, sampler) in samplers {
+        let text =
+            model.generate(prompt, GENERATED, &sampler);
+        println!(
+            "{name}, {sampler_name}: {}|{}",
+            vocab.decode(prompt),
+            vocab.decode(&text)
+        );
+    }
+    dbg_thread.join().unwrap();
+}
+
+pub fn train_and_validate_text_charlm() {
+    let (train, test) = get_data_text("data/text.txt", 0.9)
+        .expect("Could not read data from text file");
+    let vocab = CharVocab::new(&(train.clone() + &test));
+    let train = vocab.encode(&train);
+    let test = vocab.encode(&test);
+    assert!(
+        train.len() > 1 && test.len() > 1,
+        "Not enough text to train and test on"
+    );
+
+    let tasks: Vec<Task> = vec![
+        |vocab, train, test| {
+            let rnn =
+                Lstm::<V, H, T, HV, O>::new().stateful();
+            train_and_validate(
+                "lstm", rnn, vocab, train, test,
+            )
+        },
+        |vocab, train, test| {
+            // U(-1, 1) saturates the tanh of 128 units
+            let rnn = RnnCell::<V, H, H, T, O, Tanh, false>::with_init::<
+                XavierUniform,
+                Orthogonal,
+                Zeros,
+            >()
+            .stateful();
+            train_and_validate(
+                "rnn", rnn, vocab, train, test,
+            )
+        },
+    ];
+    let seed = rng::seed();
+    std::thread::scope(|s| {
+        for (i, task) in tasks.into_iter().enumerate() {
+            let (vocab, train, test) =
+                (&vocab, &train, &test);
+            // the recurrent layers keep every timestep on the
+            // stack
+            std::thread::Builder::new()
+                .stack_size(64 * 1024 * 1024)
+                .spawn_scoped(s, move || {
+                    rng::reseed(rng::worker_seed(seed, i));
+                    task(vocab, train, test)
+                })
+                .unwrap();
+        }
+    });
+}
@@ -14,6 +14,7 @@ use crate::models::cnn::DIGITS;
 use crate::models::cnn::MNIST_IMAGE_DIM;
 use crate::models::NNClassifierModel;
 use crate::optimizers::adam::AdamFactory;
+use crate::rng;
 use crate::runners::write_costs_to_file;
 
 fn preprocess_narray_to_nalgebra(
@@ -138,6 +139,10 @@ pub fn train_and_validate_mnist_cnn() {
         (0..6)
             .into_par_iter()
             .map(|i| {
+                rng::reseed(rng::worker_seed(
+                    rng::seed(),
+                    i,
+                ));
                 let (tx, rx) = mpsc::channel();
                 let mut model =
                     NNClassifierModel::<
@@ -187,7 +192,8 @@ pub fn train_and_validate_mnist_cnn() {
                         >,
                         */
                         10,
-                    >::new(Some(tx));
+                    >::new(Some(tx))
+                    .with_ema(0.999);
                 let dbg_thread =
                     std::thread::spawn(move || {
                         write_costs_to_file(
@@ -196,6 +202,7 @@ pub fn train_and_validate_mnist_cnn() {
                         );
                     });
                 model.train(&x_train, &y_train);
+                model.finish();
                 println!("");
                 (
                     i,
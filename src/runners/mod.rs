@@ -5,8 +5,11 @@ use std::sync::mpsc::Receiver;
 use csv::ReaderBuilder;
 
 pub mod annrun;
+pub mod charlmrun;
 pub mod cnnrun;
 pub mod rnnrun;
+pub mod seq2seqrun;
+pub mod tagrun;
 
 fn write_costs_to_file(file: &str, recv: Receiver<f32>) {
     let mut costs = vec![];
@@ -13,11 +13,15 @@ use rayon::ThreadPoolBuilder;
 use regex::Regex;
 
 use crate::layers::embedding::Embedding;
+use crate::layers::masked::Masked;
+use crate::models::grusent::GruSentAnalyzer;
 use crate::models::lstmsent::LstmSentAnalyzer;
 use crate::models::rnnsent::RnnSentimentAnalyzer;
 use crate::models::transformer1::Transformer1;
 use crate::models::NNClassifierModel;
 use crate::optimizers::adam::AdamFactory;
+use crate::optimizers::clip::GradClip;
+use crate::rng;
 use crate::runners::write_costs_to_file;
 
 const N: usize = 50;
@@ -46,50 +50,69 @@ pub fn train_and_validate_imdb_rnn() {
         (0..3)
             .into_par_iter()
             .map(|i| {
+                rng::reseed(rng::worker_seed(
+                    rng::seed(),
+                    i,
+                ));
                 let (tx, rx) = mpsc::channel();
 
-                let mut model =
-                    NNClassifierModel::<
-                        RnnSentimentAnalyzer<
-                            N,
-                            M,
-                            2,
-                            AdamFactory<
-                                1,
-                                100,
-                                95,
-                                100,
-                                95,
-                                100,
-                            >,
+                let mut model = NNClassifierModel::<
+                    RnnSentimentAnalyzer<
+                        N,
+                        M,
+                        2,
+                        AdamFactory<
+                            1,
+                            100,
+                            95,
+                            100,
+                            95,
+                            100,
                         >,
-                        /*
-                        LstmSentAnalyzer<
-                            //SgdFactory<1, 1000>,
-                            AdamFactory<
-                                1,
-                                1000,
-                                95,
-                                100,
-                                95,
-                                100,
-                            >,
+                        AdamFactory<1, 100, 9, 10, 9, 10>,
+                    >,
+                    /*
+                    LstmSentAnalyzer<
+                        //SgdFactory<1, 1000>,
+                        AdamFactory<
+                            1,
+                            1000,
+                            95,
+                            100,
+                            95,
+                            100,
                         >,
-                        */
-                        /*
-                        Transformer1<
-                            AdamFactory<
-                                1,
-                                100,
-                                95,
-                                100,
-                                95,
-                                100,
-                            >,
+                    >,
+                    */
+                    /*
+                    GruSentAnalyzer<
+                        AdamFactory<
+                            1,
+                            1000,
+                            95,
+                            100,
+                            95,
+                            100,
                         >,
-                        */
-                        2,
-                    >::new(Some(tx));
+                    >,
+                    */
+                    /*
+                    Transformer1<
+                        AdamFactory<
+                            1,
+                            100,
+                            95,
+                            100,
+                            95,
+                            100,
+                        >,
+                    >,
+                    */
+                    2,
+                >::new(
+                    Some(tx)
+                )
+                .with_grad_clip(GradClip::GlobalNorm(5.));
                 let dbg_thread =
                     std::thread::spawn(move || {
                         write_costs_to_file(
@@ -98,6 +121,7 @@ pub fn train_and_validate_imdb_rnn() {
                         );
                     });
                 model.train(&x_train, &y_train);
+                model.finish();
                 println!("");
                 (
                     i,
@@ -119,9 +143,9 @@ fn get_data_csv(
     file_path: &str,
     train_test_ratio: f32,
 ) -> anyhow::Result<(
-    Vec<SMatrix<f32, N, M>>,
+    Vec<Masked<N, M>>,
     Vec<SVector<f32, 2>>,
-    Vec<SMatrix<f32, N, M>>,
+    Vec<Masked<N, M>>,
     Vec<SVector<f32, 2>>,
 )> {
     let file = File::open(file_path)?;
@@ -0,0 +1,131 @@
+use std::sync::mpsc;
+
+use rand::Rng;
+
+use crate::layers::crossattention::Additive;
+use crate::layers::crossattention::Multiplicative;
+use crate::layers::crossattention::Score;
+use crate::layers::Parameters;
+use crate::models::beam::BeamSearch;
+use crate::models::seq2seq::Seq2Seq;
+use crate::models::seq2seq::EOS;
+use crate::models::seq2seq::H;
+use crate::models::seq2seq::S;
+use crate::models::seq2seq::T;
+use crate::optimizers::adam::AdamFactory;
+use crate::optimizers::clip::GradClip;
+use crate::rng;
+use crate::runners::write_costs_to_file;
+
+type O = AdamFactory<1, 1000, 9, 10, 999, 1000>;
+
+const TRAIN: usize = 20000;
+const TEST: usize = 500;
+const EPOCHS: usize = 3;
+const BEAM: usize = 4;
+
+// random digit sequences and their reversal
+fn reversal_data(
+    n: usize,
+) -> Vec<(Vec<usize>, Vec<usize>)> {
+    rng::with_rng(|rng| {
+        (0..n)
+            .map(|_| {
+                let len = rng.gen_range(3..=S);
+                let src = (0..len)
+                    .map(|_| rng.gen_range(0..10))
+                    .collect::<Vec<_>>();
+                let tgt =
+                    src.iter().rev().copied().collect();
+                (src, tgt)
+            })
+            .collect()
+    })
+}
+
+fn train_and_validate<A: Score<H, H>>(name: &str) {
+    let train = reversal_data(TRAIN);
+    let test = reversal_data(TEST);
+
+    let (tx, rx) = mpsc::channel();
+    let dbg_thread = {
+        let file = format!("seq2seq-{name}.txt");
+        std::thread::spawn(move || {
+            write_costs_to_file(&file, rx)
+        })
+    };
+    let mut model = Seq2Seq::<A, O>::new();
+    let clip = GradClip::GlobalNorm(5.);
+    for epoch in 0..EPOCHS {
+        let mut cost = 0.;
+        for (i, (src, tgt)) in train.iter().enumerate() {
+            cost += model.train_step(src, tgt);
+            clip.apply(&mut model);
+            model.update_params();
+            if (i + 1) % 100 == 0 {
+                tx.send(cost / 100.).unwrap();
+                cost = 0.;
+            }
+        }
+        println!("{name}: epoch {epoch} done");
+    }
+    model.finish_params();
+    drop(tx);
+
+    let correct = test
+        .iter()
+        .filter(|(src, tgt)| model.translate(src) == *tgt)
+        .count();
+    println!(
+        "Seq2seq {name} attention, exact match: {:.3}%",
+        correct as f32 / TEST as f32 * 100.
+    );
+    let beam = BeamSearch::new(BEAM, T).eos(EOS);
+    let correct = test
+        .iter()
+        .filter(|(src, tgt)| {
+            model.translate_beam(src, &beam) == *tgt
+        })
+        .count();
+    println!(
+        "Seq2seq {name} attention, beam {BEAM}, exact \
+         match: {:.3}%",
+        correct as f32 / TEST as f32 * 100.
+    );
+    dbg_thread.join().unwrap();
+}
+
+pub fn train_and_validate_reversal_seq2seq() {
+    let tasks: Vec<fn()> = vec![
+        || {
+            train_and_validate::<Additive<H, H, H, O>>(
+                "additive",
+            )
+        },
+        || {
+            train_and_validate::<Multiplicative<H, H, O>>(
+                "multiplicative",
+            )
+        },
+    ];
+    let seed = rng::seed();
+    tasks
+        .into_iter()
+        .enumerate()
+        .map(|(i, task)| {
+            // the recurrent layers keep every timestep on the
+            // stack
+            std::thread::Builder::new()
+                .stack_size(256 * 1024 * 1024)
+                .spawn(move || {
+                    rng::reseed(rng::worker_seed(seed, i));
+                    task()
+                })
+                .unwrap()
+        })
+        .collect::<Vec<_>>()
+        .into_iter()
+        .for_each(|task| {
+            task.join().expect("A task failed")
+        });
+}
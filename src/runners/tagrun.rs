@@ -0,0 +1,123 @@
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+use std::sync::mpsc;
+
+use crate::dataset::get_data_conll;
+use crate::dataset::TaggedSentence;
+use crate::models::lstmtagger::LstmTagger;
+use crate::models::lstmtagger::N;
+use crate::models::NNTaggerModel;
+use crate::optimizers::adam::AdamFactory;
+use crate::optimizers::clip::GradClip;
+use crate::runners::write_costs_to_file;
+
+// ner tags of CoNLL-2003 (O and B-/I- of PER, ORG, LOC, MISC)
+const TAGS: usize = 9;
+const TAG_COLUMN: usize = 3;
+
+pub fn train_and_validate_conll_tagger() {
+    println!("Data preprocessing START");
+
+    let (train, test) =
+        get_data_conll("data/conll.txt", TAG_COLUMN, 0.8)
+            .expect("Could not read data from conll file");
+
+    // tag names sorted, their position is the tag index
+    let tags = train
+        .iter()
+        .chain(test.iter())
+        .flat_map(|(_, tags)| tags.iter().cloned())
+        .collect::<BTreeSet<String>>()
+        .into_iter()
+        .collect::<Vec<_>>();
+    assert!(
+        tags.len() <= TAGS,
+        "Found {} tags but the model has {TAGS}",
+        tags.len()
+    );
+
+    // token 0 stands for the words seen at most once in training,
+    // so that the model learns an embedding for unknown words. the
+    // words are sorted for the ids to be the same on every run
+    let mut counts = BTreeMap::<String, usize>::new();
+    for word in train.iter().flat_map(|(words, _)| words) {
+        *counts.entry(word.to_lowercase()).or_default() +=
+            1;
+    }
+    let mut vocab = HashMap::<String, usize>::new();
+    for (word, count) in counts {
+        if count > 1 {
+            let id = vocab.len() + 1;
+            vocab.insert(word, id);
+        }
+    }
+    let vocab_size = vocab.len() + 1;
+
+    let preprocess = |sentences: Vec<TaggedSentence>| {
+        let mut x = Vec::<Vec<usize>>::new();
+        let mut y = Vec::<[usize; N]>::new();
+        for (words, sentence_tags) in sentences {
+            x.push(
+                words
+                    .iter()
+                    .map(|word| {
+                        vocab
+                            .get(&word.to_lowercase())
+                            .copied()
+                            .unwrap_or(0)
+                    })
+                    .collect(),
+            );
+            let mut t = [0; N];
+            sentence_tags
+                .iter()
+                .take(N)
+                .enumerate()
+                .for_each(|(i, tag)| {
+                    t[i] = tags
+                        .iter()
+                        .position(|s| s == tag)
+                        .unwrap();
+                });
+            y.push(t);
+        }
+        (x, y)
+    };
+    let (x_train, y_train) = preprocess(train);
+    let (x_test, y_test) = preprocess(test);
+
+    println!("Data preprocessing DONE");
+
+    // the recurrent layers keep every timestep on the stack
+    let task = std::thread::Builder::new()
+        .stack_size(1024 * 1024 * 1024)
+        .spawn(move || {
+            let (tx, rx) = mpsc::channel();
+            let mut model = NNTaggerModel::<
+                LstmTagger<
+                    TAGS,
+                    AdamFactory<1, 1000, 9, 10, 99, 100>,
+                >,
+                N,
+                TAGS,
+            >::new(
+                vocab_size, Some(tx)
+            )
+            .with_grad_clip(GradClip::GlobalNorm(5.));
+            let dbg_thread =
+                std::thread::spawn(move || {
+                    write_costs_to_file("tagger.txt", rx);
+                });
+            model.train(&x_train, &y_train);
+            model.finish();
+            println!();
+            let score = model.validate(&x_test, &y_test);
+            (score, dbg_thread)
+        })
+        .unwrap();
+    let (score, dbg_thread) =
+        task.join().expect("Training failed");
+    println!("Tagger token accuracy: {:.3}%", score * 100.);
+    dbg_thread.join().unwrap();
+}